@@ -0,0 +1,49 @@
+#![feature(test)]
+
+extern crate test;
+
+use nanoxml::de::XmlParser;
+use test::Bencher;
+
+fn document(records: usize) -> String {
+    let mut s = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<records>\n");
+    for i in 0..records {
+        s.push_str(&format!(
+            "  <!-- record {i} -->\n  <record id=\"{i}\" name=\"Record number {i} &amp; friends\" lang=\"dé\">\n    <title>Ħ€lłö Wø®lð, this is record {i}</title>\n    <body>Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua &lt;{i}&gt;.</body>\n    <empty/>\n  </record>\n"
+        ));
+    }
+    s.push_str("</records>\n");
+    s
+}
+
+fn tokenize(s: &str) -> usize {
+    let mut xml = XmlParser::new(s).unwrap();
+    let mut count = 0;
+    while xml.next_token().unwrap().is_some() {
+        count += 1;
+    }
+    count
+}
+
+#[bench]
+fn tokenize_large(b: &mut Bencher) {
+    let s = document(10_000);
+    b.bytes = s.len() as u64;
+    b.iter(|| tokenize(test::black_box(&s)));
+}
+
+#[bench]
+fn parse_text_large(b: &mut Bencher) {
+    let s = document(10_000);
+    b.bytes = s.len() as u64;
+    b.iter(|| {
+        let mut xml = XmlParser::new(test::black_box(&s)).unwrap();
+        let mut len = 0;
+        while let Some(token) = xml.next_token().unwrap() {
+            if let nanoxml::de::XmlToken::Text(text) = token {
+                len += text.parsed().len();
+            }
+        }
+        len
+    });
+}
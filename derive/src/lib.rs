@@ -8,21 +8,34 @@ use syn::punctuated::Punctuated;
 use syn::token::Comma;
 use syn::{Attribute, Data, DataEnum, DataStruct, DeriveInput, Expr, Field};
 use syn::{Fields, GenericParam, Generics, Ident, Lifetime, LifetimeParam};
-use syn::{Lit, LitStr, Type, Variant};
+use syn::{GenericArgument, Lit, LitStr, PathArguments, Type, Variant};
 
 #[cfg(feature = "ser")]
 #[proc_macro_derive(SerXml, attributes(nanoxml))]
 pub fn derive_serxml(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
-    let rename = get_rename_attr(&input.attrs).unwrap_or(input.ident.to_string());
+    let container = get_container_attrs(&input.attrs);
+    let rename = container.rename.unwrap_or(input.ident.to_string());
     match input.data {
         Data::Struct(DataStruct {
             fields: Fields::Named(ref fields),
             ..
-        }) => derive_serxml_struct(&input.ident, &rename, &fields.named, input.generics),
+        }) if !container.unit_element && container.tag.is_none() => {
+            derive_serxml_struct(&input.ident, &rename, &fields.named, input.generics)
+        }
+        Data::Enum(DataEnum { variants, .. }) if let Some(tag) = &container.tag => {
+            derive_serxml_tagged_enum(&input.ident, &rename, tag, &variants, input.generics)
+        }
+        Data::Enum(DataEnum { variants, .. })
+            if container.unit_element || is_element_enum(&variants) =>
+        {
+            derive_serxml_element_enum(&input.ident, &rename, &variants, input.generics)
+        }
         Data::Enum(DataEnum { variants, .. }) => {
             derive_serxml_enum(&input.ident, &rename, &variants)
         }
+        Data::Struct(_) if container.unit_element => panic!("unit attr is only valid for enums"),
+        Data::Struct(_) if container.tag.is_some() => panic!("tag attr is only valid for enums"),
         _ => panic!("SerXml can only be derived for structs with named fields or enums"),
     }
 }
@@ -38,45 +51,17 @@ fn derive_serxml_struct(
 
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-    let ser_text = xml_fields
-        .iter()
-        .find(|f| f.field_kind == FieldKind::Text)
-        .filter(|f| !f.skip_ser)
-        .map(|f| {
-            let field_name = f.field_name;
-            quote! { ::nanoxml::derive::ser::SerXmlAsAttr::ser_as_text(&self.#field_name, __xml)?; }
-        });
-
-    let ser_regular = xml_fields
-        .iter()
-        .filter(|f| f.field_kind == FieldKind::Regular)
-        .filter(|f| !f.skip_ser)
-        .map(|f| {
-            let field_name = f.field_name;
-            let renamed = &f.renamed;
-            quote! { ::nanoxml::derive::ser::SerXml::ser_xml(&self.#field_name, __xml, #renamed)?; }
-        });
-
-    let ser_attrs = xml_fields
-        .iter()
-        .filter(|f| f.field_kind == FieldKind::Attr)
-        .filter(|f| !f.skip_ser)
-        .map(|f| {
-            let field_name = f.field_name;
-            let renamed = &f.renamed;
-            quote! { ::nanoxml::derive::ser::SerXmlAsAttr::ser_as_attr(&self.#field_name, __xml, #renamed)?; }
-        });
+    let (ser_body, ser_attrs) = ser_fields(&xml_fields, |f| quote! { &self.#f });
 
     let serxml_impl = quote! {
         impl #impl_generics ::nanoxml::derive::ser::SerXml for #name #ty_generics #where_clause {
             fn ser_body<W: ::core::fmt::Write>(&self, __xml: &mut ::nanoxml::ser::XmlBuilder<'_, W>) -> ::core::fmt::Result {
-                #ser_text
-                #(#ser_regular)*
+                #ser_body
                 Ok(())
             }
 
             fn ser_attrs<W: ::core::fmt::Write>(&self, __xml: &mut ::nanoxml::ser::XmlBuilder<'_, W>) -> ::core::fmt::Result {
-                #(#ser_attrs)*
+                #ser_attrs
                 Ok(())
             }
         }
@@ -101,6 +86,83 @@ fn derive_serxml_struct(
     full_impl.into()
 }
 
+#[cfg(feature = "ser")]
+fn ser_fields(
+    xml_fields: &[XmlField<'_>],
+    access: impl Fn(&Ident) -> proc_macro2::TokenStream,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    let ser_text = xml_fields
+        .iter()
+        .find(|f| f.field_kind == FieldKind::Text)
+        .filter(|f| !f.skip_ser)
+        .map(|f| {
+            let value = access(f.field_name);
+            match f.list {
+                true => quote! { ::nanoxml::derive::ser::SerXmlList::ser_list(#value, __xml)?; },
+                false => {
+                    quote! { ::nanoxml::derive::ser::SerXmlAsAttr::ser_as_text(#value, __xml)?; }
+                }
+            }
+        });
+
+    let ser_regular = xml_fields
+        .iter()
+        .filter(|f| f.field_kind == FieldKind::Regular)
+        .filter(|f| !f.skip_ser)
+        .map(|f| {
+            let value = access(f.field_name);
+            let renamed = &f.renamed;
+            let key = &f.key;
+            if let Some(wrapper) = &f.wrapper {
+                let wrapped = quote! {
+                    __xml.tag_open(#wrapper)?;
+                    ::nanoxml::derive::ser::SerXml::ser_xml(__items, __xml, #renamed)?;
+                    __xml.tag_close(#wrapper)?;
+                };
+                return match is_option(f.real_type) {
+                    true => quote! {
+                        if let Some(__items) = #value {
+                            #wrapped
+                        }
+                    },
+                    false => quote! {
+                        {
+                            let __items = #value;
+                            #wrapped
+                        }
+                    },
+                };
+            }
+            match f.map {
+                Some(MapLayout::Entry) => quote! { ::nanoxml::derive::ser::SerXmlMap::ser_map_entries(#value, __xml, #renamed, #key)?; },
+                Some(_) => quote! { ::nanoxml::derive::ser::SerXmlMap::ser_map_tags(#value, __xml)?; },
+                None => quote! { ::nanoxml::derive::ser::SerXml::ser_field(#value, __xml, #renamed)?; },
+            }
+        });
+
+    let ser_attrs = xml_fields
+        .iter()
+        .filter(|f| f.field_kind == FieldKind::Attr)
+        .filter(|f| !f.skip_ser)
+        .map(|f| {
+            let value = access(f.field_name);
+            let renamed = &f.renamed;
+            match (f.map, f.list) {
+                (Some(_), _) => quote! { ::nanoxml::derive::ser::SerXmlMap::ser_map_attrs(#value, __xml)?; },
+                (None, true) => quote! { ::nanoxml::derive::ser::SerXmlList::ser_list_as_attr(#value, __xml, #renamed)?; },
+                (None, false) => quote! { ::nanoxml::derive::ser::SerXmlAsAttr::ser_as_attr(#value, __xml, #renamed)?; },
+            }
+        });
+
+    let ser_body = quote! {
+        #ser_text
+        #(#ser_regular)*
+    };
+    let ser_attrs = quote! { #(#ser_attrs)* };
+
+    (ser_body, ser_attrs)
+}
+
 #[cfg(feature = "ser")]
 fn derive_serxml_enum(
     name: &Ident,
@@ -149,19 +211,174 @@ fn derive_serxml_enum(
     full_impl.into()
 }
 
+#[cfg(feature = "ser")]
+fn derive_serxml_element_enum(
+    name: &Ident,
+    rename: &str,
+    variants: &Punctuated<Variant, Comma>,
+    generics: Generics,
+) -> TokenStream {
+    let variants = get_xml_variants(variants);
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let xml_cases = variants.iter().map(|v| {
+        let variant_name = v.variant_name;
+        let renamed = &v.renamed;
+        match v.fields {
+            Fields::Unit => quote! { Self::#variant_name => __xml.tag_empty(#renamed), },
+            Fields::Unnamed(_) => quote! { Self::#variant_name(__value) => ::nanoxml::derive::ser::SerXml::ser_xml(__value, __xml, #renamed), },
+            Fields::Named(fields) => {
+                let xml_fields = get_xml_fields(&fields.named);
+                let (ser_body, ser_attrs) = ser_fields(&xml_fields, |f| quote! { #f });
+                let bindings = xml_fields
+                    .iter()
+                    .filter(|f| !f.skip_ser)
+                    .map(|f| f.field_name);
+                quote! {
+                    Self::#variant_name { #(#bindings,)* .. } => {
+                        __xml.tag_open_start(#renamed)?;
+                        #ser_attrs
+                        __xml.tag_open_end()?;
+                        #ser_body
+                        __xml.tag_close(#renamed)
+                    }
+                }
+            }
+        }
+    });
+
+    // the variant element is the content, so that fields of this type keep their own element
+    let full_impl = quote! {
+        impl #impl_generics ::nanoxml::derive::ser::SerXml for #name #ty_generics #where_clause {
+            fn ser_body<W: ::core::fmt::Write>(&self, __xml: &mut ::nanoxml::ser::XmlBuilder<'_, W>) -> ::core::fmt::Result {
+                ::nanoxml::derive::ser::SerXml::ser_xml(self, __xml, "")
+            }
+
+            fn ser_attrs<W: ::core::fmt::Write>(&self, __xml: &mut ::nanoxml::ser::XmlBuilder<'_, W>) -> ::core::fmt::Result {
+                _ = __xml;
+                Ok(())
+            }
+
+            fn ser_xml<W: ::core::fmt::Write>(&self, __xml: &mut ::nanoxml::ser::XmlBuilder<'_, W>, _: &str) -> ::core::fmt::Result {
+                match self {
+                    #(#xml_cases)*
+                }
+            }
+
+            fn ser_field<W: ::core::fmt::Write>(&self, __xml: &mut ::nanoxml::ser::XmlBuilder<'_, W>, __tag_name: &str) -> ::core::fmt::Result {
+                __xml.tag_open(__tag_name)?;
+                ::nanoxml::derive::ser::SerXml::ser_xml(self, __xml, "")?;
+                __xml.tag_close(__tag_name)
+            }
+        }
+
+        impl #impl_generics ::nanoxml::derive::ser::SerXmlTopLevel for #name #ty_generics #where_clause {
+            const TAG_NAME: &'static str = #rename;
+        }
+    };
+
+    full_impl.into()
+}
+
+#[cfg(feature = "ser")]
+fn derive_serxml_tagged_enum(
+    name: &Ident,
+    rename: &str,
+    tag: &str,
+    variants: &Punctuated<Variant, Comma>,
+    generics: Generics,
+) -> TokenStream {
+    let variants = get_xml_variants(variants);
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let mut body_cases = Vec::new();
+    let mut attrs_cases = Vec::new();
+
+    for v in &variants {
+        let variant_name = v.variant_name;
+        let renamed = &v.renamed;
+        let fields = match v.fields {
+            Fields::Named(fields) => get_xml_fields(&fields.named),
+            Fields::Unit => Vec::new(),
+            Fields::Unnamed(_) => {
+                panic!("tagged enum variants must be unit variants or struct variants")
+            }
+        };
+        let (ser_body, ser_attrs) = ser_fields(&fields, |f| quote! { #f });
+        let body_bindings = fields
+            .iter()
+            .filter(|f| f.field_kind != FieldKind::Attr && !f.skip_ser)
+            .map(|f| f.field_name);
+        let attrs_bindings = fields
+            .iter()
+            .filter(|f| f.field_kind == FieldKind::Attr && !f.skip_ser)
+            .map(|f| f.field_name);
+        body_cases.push(quote! {
+            Self::#variant_name { #(#body_bindings,)* .. } => {
+                #ser_body
+                Ok(())
+            }
+        });
+        attrs_cases.push(quote! {
+            Self::#variant_name { #(#attrs_bindings,)* .. } => {
+                __xml.attr(#tag, #renamed)?;
+                #ser_attrs
+                Ok(())
+            }
+        });
+    }
+
+    let full_impl = quote! {
+        impl #impl_generics ::nanoxml::derive::ser::SerXml for #name #ty_generics #where_clause {
+            fn ser_body<W: ::core::fmt::Write>(&self, __xml: &mut ::nanoxml::ser::XmlBuilder<'_, W>) -> ::core::fmt::Result {
+                match self {
+                    #(#body_cases)*
+                }
+            }
+
+            fn ser_attrs<W: ::core::fmt::Write>(&self, __xml: &mut ::nanoxml::ser::XmlBuilder<'_, W>) -> ::core::fmt::Result {
+                match self {
+                    #(#attrs_cases)*
+                }
+            }
+        }
+
+        impl #impl_generics ::nanoxml::derive::ser::SerXmlTopLevel for #name #ty_generics #where_clause {
+            const TAG_NAME: &'static str = #rename;
+        }
+    };
+
+    full_impl.into()
+}
+
 #[cfg(feature = "de")]
 #[proc_macro_derive(DeXml, attributes(nanoxml))]
 pub fn derive_dexml(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
-    let rename = get_rename_attr(&input.attrs).unwrap_or(input.ident.to_string());
+    let container = get_container_attrs(&input.attrs);
+    let rename = container.rename.unwrap_or(input.ident.to_string());
     match input.data {
         Data::Struct(DataStruct {
             fields: Fields::Named(ref fields),
             ..
-        }) => derive_dexml_struct(&input.ident, &rename, &fields.named, input.generics),
+        }) if !container.unit_element && container.tag.is_none() => {
+            derive_dexml_struct(&input.ident, &rename, &fields.named, input.generics)
+        }
+        Data::Enum(DataEnum { variants, .. }) if let Some(tag) = &container.tag => {
+            derive_dexml_tagged_enum(&input.ident, &rename, tag, &variants, input.generics)
+        }
+        Data::Enum(DataEnum { variants, .. })
+            if container.unit_element || is_element_enum(&variants) =>
+        {
+            derive_dexml_element_enum(&input.ident, &rename, &variants, input.generics)
+        }
         Data::Enum(DataEnum { variants, .. }) => {
             derive_dexml_enum(&input.ident, &rename, &variants)
         }
+        Data::Struct(_) if container.unit_element => panic!("unit attr is only valid for enums"),
+        Data::Struct(_) if container.tag.is_some() => panic!("tag attr is only valid for enums"),
         _ => panic!("DeXml can only be derived for structs with named fields or enums"),
     }
 }
@@ -177,27 +394,270 @@ fn derive_dexml_struct(
 
     let xml_fields = get_xml_fields(fields);
 
-    let mut generics_clone = generics.clone();
-    let lifetime_param = match generics.lifetimes().next() {
-        Some(lt) => quote! { <#lt> },
-        None => {
-            generics_clone
-                .params
-                .push(GenericParam::Lifetime(LifetimeParam::new(Lifetime::new(
-                    "'a",
-                    Span::call_site(),
-                ))));
-            quote! { <'a> }
-        }
-    };
+    let (generics_clone, lifetime_param) = with_de_lifetime(&generics);
     let (impl_generics, _, _) = generics_clone.split_for_impl();
     let (_, ty_generics, where_clause) = generics.split_for_impl();
 
+    let de_fields = de_fields(&xml_fields, quote! { Self }, None);
+
+    let attr_map = xml_fields.iter().find(|f| f.map == Some(MapLayout::Attr));
+    let tag_map = xml_fields.iter().find(|f| f.map == Some(MapLayout::Tag));
+
+    let in_place_init = xml_fields.iter().map(|f| {
+        let field_name = f.field_name;
+        let real_type = f.real_type;
+        let seen = format_ident!("__seen_{field_name}");
+        match (f.field_type, f.append) {
+            (FieldType::Seq, true) => quote! { let mut #field_name = <#real_type as ::nanoxml::derive::de::DeXmlSeq>::new_seq(); },
+            (FieldType::Seq, false) => quote! {
+                let mut #field_name = <#real_type as ::nanoxml::derive::de::DeXmlSeq>::new_seq();
+                let mut #seen = false;
+            },
+            (FieldType::Map, _) => quote! {
+                let mut #field_name = <#real_type as ::nanoxml::derive::de::DeXmlMap>::new_map();
+                let mut #seen = false;
+            },
+            _ => quote! { let mut #seen = false; },
+        }
+    });
+
+    let in_place_attr = xml_fields
+        .iter()
+        .filter(|f| f.field_kind == FieldKind::Attr)
+        .filter(|f| f.field_type != FieldType::Map)
+        .map(|f| {
+            let field_name = f.field_name;
+            let renamed = &f.renamed;
+            let seen = format_ident!("__seen_{field_name}");
+            let value = de_value(f, quote! { __attr_value });
+            let value = match f.field_type {
+                FieldType::Option => quote! { Some(#value) },
+                _ => value,
+            };
+            quote! {
+                #renamed => {
+                    if #seen {
+                        return Err(::nanoxml::de::XmlError::DuplicateField);
+                    }
+                    #seen = true;
+                    self.#field_name = #value;
+                }
+            }
+        });
+
+    let in_place_text = xml_fields
+        .iter()
+        .find(|f| f.field_kind == FieldKind::Text)
+        .map(|f| {
+            let field_name = f.field_name;
+            let value = de_value(f, quote! { __parser.text()? });
+            let value = match f.field_type {
+                FieldType::Option => quote! { Some(#value) },
+                _ => value,
+            };
+            quote! {
+                self.#field_name = #value;
+                __parser.tag_close()?;
+            }
+        });
+
+    let in_place_regular = xml_fields
+        .iter()
+        .filter(|f| f.field_kind == FieldKind::Regular)
+        .map(|f| {
+            let field_name = f.field_name;
+            let real_type = f.real_type;
+            let renamed = &f.renamed;
+            let seen = format_ident!("__seen_{field_name}");
+            let dup_check = quote! {
+                if #seen {
+                    return Err(::nanoxml::de::XmlError::DuplicateField);
+                }
+                #seen = true;
+            };
+            match (f.field_type, f.append) {
+                (FieldType::Seq, true) => {
+                    let (tag, push) = de_seq_push(f);
+                    quote! { #tag => #push, }
+                }
+                (FieldType::Seq, false) => {
+                    let (tag, push) = de_seq_push(f);
+                    quote! {
+                        #tag => {
+                            #seen = true;
+                            #push;
+                        }
+                    }
+                }
+                (FieldType::Option, _) => quote! {
+                    #renamed => {
+                        #dup_check
+                        match &mut self.#field_name {
+                            Some(__value) => ::nanoxml::derive::de::DeXml::de_xml_field_in_place(__value, __parser)?,
+                            None => self.#field_name = Some(::nanoxml::derive::de::DeXml::de_xml_field(__parser)?),
+                        }
+                    }
+                },
+                (FieldType::Regular, _) => quote! {
+                    #renamed => {
+                        #dup_check
+                        ::nanoxml::derive::de::DeXml::de_xml_field_in_place(&mut self.#field_name, __parser)?;
+                    }
+                },
+                (FieldType::Map, _) => match f.map {
+                    Some(MapLayout::Entry) => {
+                        let key = &f.key;
+                        quote! {
+                            #renamed => {
+                                #seen = true;
+                                <#real_type as ::nanoxml::derive::de::DeXmlMap>::push_entry(&mut #field_name, __parser, #key)?;
+                            }
+                        }
+                    }
+                    _ => quote! {},
+                },
+            }
+        });
+
+    let in_place_matches = xml_fields
+        .iter()
+        .filter(|f| f.field_kind == FieldKind::Regular)
+        .filter_map(|f| {
+            let field_name = f.field_name;
+            let seen = format_ident!("__seen_{field_name}");
+            let matches = de_matches_tag(f)?;
+            let (_, push) = de_seq_push(f);
+            match f.append {
+                true => Some(quote! { __tag if #matches => #push, }),
+                false => Some(quote! {
+                    __tag if #matches => {
+                        #seen = true;
+                        #push;
+                    }
+                }),
+            }
+        });
+
+    let in_place_attr_fallback = match attr_map {
+        Some(f) => {
+            let field_name = f.field_name;
+            let real_type = f.real_type;
+            let seen = format_ident!("__seen_{field_name}");
+            quote! {
+                __attr_key => {
+                    #seen = true;
+                    <#real_type as ::nanoxml::derive::de::DeXmlMap>::push_attr(&mut #field_name, __attr_key, __attr_value)?;
+                }
+            }
+        }
+        None => quote! { _ => return Err(::nanoxml::de::XmlError::InvalidField), },
+    };
+
+    let in_place_tag_fallback = match tag_map {
+        Some(f) => {
+            let field_name = f.field_name;
+            let real_type = f.real_type;
+            let seen = format_ident!("__seen_{field_name}");
+            quote! {
+                __tag => {
+                    #seen = true;
+                    <#real_type as ::nanoxml::derive::de::DeXmlMap>::push_tag(&mut #field_name, __parser, __tag)?;
+                }
+            }
+        }
+        None => quote! { _ => return Err(::nanoxml::de::XmlError::InvalidField), },
+    };
+
+    let in_place_body = match in_place_text {
+        Some(in_place_text) => in_place_text,
+        None => quote! {
+            while let Ok((__tag)) = __parser.tag_open_or_close()? {
+                match __tag {
+                    #(#in_place_regular)*
+                    #(#in_place_matches)*
+                    #in_place_tag_fallback
+                }
+            }
+        },
+    };
+
+    let in_place_finish = xml_fields
+        .iter()
+        .filter(|f| matches!(f.field_type, FieldType::Seq | FieldType::Map))
+        .map(|f| {
+            let field_name = f.field_name;
+            let seen = format_ident!("__seen_{field_name}");
+            if f.field_type == FieldType::Map {
+                return quote! {
+                    if #seen {
+                        self.#field_name = #field_name;
+                    }
+                };
+            }
+            match f.append {
+                true => quote! { ::nanoxml::derive::de::DeXmlSeq::append(&mut self.#field_name, #field_name)?; },
+                false => quote! {
+                    if #seen {
+                        self.#field_name = ::nanoxml::derive::de::DeXmlSeq::finish(#field_name)?;
+                    }
+                },
+            }
+        });
+
+    let dexml_impl = quote! {
+        impl #lifetime_param ::nanoxml::derive::de::DeXml #lifetime_param for #name #ty_generics #where_clause {
+            fn de_xml(__parser: &mut ::nanoxml::de::XmlParser<'a>) -> Result<Self, ::nanoxml::de::XmlError> {
+                #de_fields
+            }
+
+            fn de_xml_in_place(&mut self, __parser: &mut ::nanoxml::de::XmlParser<'a>) -> Result<(), ::nanoxml::de::XmlError> {
+                #(#in_place_init)*
+                while let Ok((__attr_key, __attr_value)) = __parser.attr_or_tag_open_end()? {
+                    match __attr_key {
+                        #(#in_place_attr)*
+                        #in_place_attr_fallback
+                    }
+                }
+                #in_place_body
+                #(#in_place_finish)*
+                Ok(())
+            }
+        }
+    };
+
+    let top_level_impl = quote! {
+        impl #impl_generics ::nanoxml::derive::de::DeXmlTopLevel #lifetime_param for #name #ty_generics #where_clause {
+            const TAG_NAME: &'static str = #rename;
+        }
+    };
+
+    let full_impl = quote! {
+        #dexml_impl
+        #top_level_impl
+    };
+
+    full_impl.into()
+}
+
+#[cfg(feature = "de")]
+fn de_fields(
+    xml_fields: &[XmlField<'_>],
+    ctor: proc_macro2::TokenStream,
+    skip_attr: Option<&str>,
+) -> proc_macro2::TokenStream {
+    use quote::format_ident;
+
     let field_init = xml_fields.iter().map(|f| {
         let field_name = f.field_name;
         let real_type = f.real_type;
+        let seen = format_ident!("__seen_{field_name}");
         match f.field_type {
+            FieldType::Seq if is_optional_wrapper(f) => quote! {
+                let mut #field_name = <#real_type as ::nanoxml::derive::de::DeXmlSeq>::new_seq();
+                let mut #seen = false;
+            },
             FieldType::Seq => quote! { let mut #field_name = <#real_type as ::nanoxml::derive::de::DeXmlSeq>::new_seq(); },
+            FieldType::Map => quote! { let mut #field_name = <#real_type as ::nanoxml::derive::de::DeXmlMap>::new_map(); },
             _ => quote! { let mut #field_name = None; },
         }
     });
@@ -205,15 +665,17 @@ fn derive_dexml_struct(
     let de_attr = xml_fields
         .iter()
         .filter(|f| f.field_kind == FieldKind::Attr)
+        .filter(|f| f.field_type != FieldType::Map)
         .map(|f| {
             let field_name = f.field_name;
             let renamed = &f.renamed;
+            let value = de_value(f, quote! { __attr_value });
             quote! {
                 #renamed => {
                     if #field_name.is_some() {
                         return Err(::nanoxml::de::XmlError::DuplicateField);
                     }
-                    #field_name = Some(::nanoxml::derive::de::DeXmlAttr::de_xml_attr(__attr_value)?);
+                    #field_name = Some(#value);
                 }
             }
         });
@@ -223,8 +685,9 @@ fn derive_dexml_struct(
         .find(|f| f.field_kind == FieldKind::Text)
         .map(|f| {
             let field_name = f.field_name;
+            let value = de_value(f, quote! { __parser.text()? });
             quote! {
-                #field_name = Some(::nanoxml::derive::de::DeXmlAttr::de_xml_attr(__parser.text()?)?);
+                #field_name = Some(#value);
                 __parser.tag_close()?;
             }
         });
@@ -237,27 +700,78 @@ fn derive_dexml_struct(
             let real_type = f.real_type;
             let renamed = &f.renamed;
             match f.field_type {
-                FieldType::Seq => quote! {
-                    #renamed => <#real_type as ::nanoxml::derive::de::DeXmlSeq>::push_item(&mut #field_name, __parser)?,
+                FieldType::Seq if is_optional_wrapper(f) => {
+                    let seen = format_ident!("__seen_{field_name}");
+                    let (tag, push) = de_seq_push(f);
+                    quote! {
+                        #tag => {
+                            #seen = true;
+                            #push;
+                        }
+                    }
+                }
+                FieldType::Seq => {
+                    let (tag, push) = de_seq_push(f);
+                    quote! { #tag => #push, }
+                }
+                FieldType::Map => match f.map {
+                    Some(MapLayout::Entry) => {
+                        let key = &f.key;
+                        quote! {
+                            #renamed => <#real_type as ::nanoxml::derive::de::DeXmlMap>::push_entry(&mut #field_name, __parser, #key)?,
+                        }
+                    }
+                    _ => quote! {},
                 },
                 _ => quote! {
                     #renamed => {
                         if #field_name.is_some() {
                             return Err(::nanoxml::de::XmlError::DuplicateField);
                         }
-                        #field_name = Some(::nanoxml::derive::de::DeXml::de_xml(__parser)?);
+                        #field_name = Some(::nanoxml::derive::de::DeXml::de_xml_field(__parser)?);
                     }
                 },
             }
         });
 
+    let de_matches = xml_fields
+        .iter()
+        .filter(|f| f.field_kind == FieldKind::Regular)
+        .filter_map(|f| {
+            let matches = de_matches_tag(f)?;
+            let (_, push) = de_seq_push(f);
+            Some(quote! { __tag if #matches => #push, })
+        });
+
+    let attr_map = xml_fields.iter().find(|f| f.map == Some(MapLayout::Attr));
+    let tag_map = xml_fields.iter().find(|f| f.map == Some(MapLayout::Tag));
+
+    let de_attr_fallback = match attr_map {
+        Some(f) => {
+            let field_name = f.field_name;
+            let real_type = f.real_type;
+            quote! { __attr_key => <#real_type as ::nanoxml::derive::de::DeXmlMap>::push_attr(&mut #field_name, __attr_key, __attr_value)?, }
+        }
+        None => quote! { _ => return Err(::nanoxml::de::XmlError::InvalidField), },
+    };
+
+    let de_tag_fallback = match tag_map {
+        Some(f) => {
+            let field_name = f.field_name;
+            let real_type = f.real_type;
+            quote! { __tag => <#real_type as ::nanoxml::derive::de::DeXmlMap>::push_tag(&mut #field_name, __parser, __tag)?, }
+        }
+        None => quote! { _ => return Err(::nanoxml::de::XmlError::InvalidField), },
+    };
+
     let de_body = match de_text {
         Some(de_text) => de_text,
         None => quote! {
             while let Ok((__tag)) = __parser.tag_open_or_close()? {
                 match __tag {
                     #(#de_regular)*
-                    _ => return Err(::nanoxml::de::XmlError::InvalidField),
+                    #(#de_matches)*
+                    #de_tag_fallback
                 }
             }
         },
@@ -277,36 +791,225 @@ fn derive_dexml_struct(
                     }
                 }
                 FieldType::Option => quote! { #field_name, },
+                FieldType::Seq if is_optional_wrapper(f) => {
+                    let seen = format_ident!("__seen_{field_name}");
+                    quote! {
+                        #field_name: match #seen {
+                            true => ::nanoxml::derive::de::DeXmlSeq::finish(#field_name)?,
+                            false => None,
+                        },
+                    }
+                }
                 FieldType::Seq => quote! { #field_name: ::nanoxml::derive::de::DeXmlSeq::finish(#field_name)?, },
+                FieldType::Map => quote! { #field_name, },
             }
         })
         .collect();
 
-    let dexml_impl = quote! {
+    let skip_attr = skip_attr.map(|key| quote! { #key => {} });
+
+    quote! {
+        #(#field_init)*
+        while let Ok((__attr_key, __attr_value)) = __parser.attr_or_tag_open_end()? {
+            match __attr_key {
+                #(#de_attr)*
+                #skip_attr
+                #de_attr_fallback
+            }
+        }
+        #de_body
+        Ok(#ctor { #(#field_unwraps)* })
+    }
+}
+
+#[cfg(feature = "de")]
+fn with_de_lifetime(generics: &Generics) -> (Generics, proc_macro2::TokenStream) {
+    let mut generics_clone = generics.clone();
+    let lifetime_param = match generics.lifetimes().next() {
+        Some(lt) => quote! { <#lt> },
+        None => {
+            generics_clone
+                .params
+                .push(GenericParam::Lifetime(LifetimeParam::new(Lifetime::new(
+                    "'a",
+                    Span::call_site(),
+                ))));
+            quote! { <'a> }
+        }
+    };
+    (generics_clone, lifetime_param)
+}
+
+#[cfg(feature = "de")]
+fn de_seq_push(f: &XmlField<'_>) -> (String, proc_macro2::TokenStream) {
+    let field_name = f.field_name;
+    let real_type = f.real_type;
+    let renamed = &f.renamed;
+    match &f.wrapper {
+        Some(wrapper) => (
+            wrapper.clone(),
+            quote! { <#real_type as ::nanoxml::derive::de::DeXmlSeq>::push_wrapped(&mut #field_name, __parser, #renamed)? },
+        ),
+        None => (
+            renamed.clone(),
+            quote! { <#real_type as ::nanoxml::derive::de::DeXmlSeq>::push_item(&mut #field_name, __parser)? },
+        ),
+    }
+}
+
+// an optional wrapped sequence is `None` only if its wrapper element is missing
+#[cfg(feature = "de")]
+fn is_optional_wrapper(f: &XmlField<'_>) -> bool {
+    f.wrapper.is_some() && is_option(f.real_type)
+}
+
+#[cfg(feature = "de")]
+fn de_matches_tag(f: &XmlField<'_>) -> Option<proc_macro2::TokenStream> {
+    let real_type = f.real_type;
+    match f.field_type {
+        FieldType::Seq if f.wrapper.is_none() => {
+            Some(quote! { <#real_type as ::nanoxml::derive::de::DeXmlSeq>::matches_tag(__tag) })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(feature = "de")]
+fn de_value(f: &XmlField<'_>, s: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    match f.list {
+        true => quote! { ::nanoxml::derive::de::DeXmlList::de_xml_list(#s)? },
+        false => quote! { ::nanoxml::derive::de::DeXmlAttr::de_xml_attr(#s)? },
+    }
+}
+
+#[cfg(feature = "de")]
+fn derive_dexml_element_enum(
+    name: &Ident,
+    rename: &str,
+    variants: &Punctuated<Variant, Comma>,
+    generics: Generics,
+) -> TokenStream {
+    let variants = get_xml_variants(variants);
+
+    if variants.is_empty() {
+        panic!("empty enum cannot be deserialized");
+    }
+
+    let (generics_clone, lifetime_param) = with_de_lifetime(&generics);
+    let (impl_generics, _, _) = generics_clone.split_for_impl();
+    let (_, ty_generics, where_clause) = generics.split_for_impl();
+
+    let cases = variants.iter().map(|v| {
+        let variant_name = v.variant_name;
+        let renamed = &v.renamed;
+        match v.fields {
+            Fields::Unit => quote! {
+                #renamed => {
+                    __parser.tag_open_end()?;
+                    __parser.tag_close()?;
+                    Ok(Self::#variant_name)
+                }
+            },
+            Fields::Unnamed(_) => quote! {
+                #renamed => Ok(Self::#variant_name(::nanoxml::derive::de::DeXml::de_xml(__parser)?)),
+            },
+            Fields::Named(fields) => {
+                let de_fields = de_fields(&get_xml_fields(&fields.named), quote! { Self::#variant_name }, None);
+                quote! {
+                    #renamed => {
+                        #de_fields
+                    }
+                }
+            }
+        }
+    });
+
+    let tags = variants.iter().map(|v| &v.renamed);
+
+    let full_impl = quote! {
         impl #lifetime_param ::nanoxml::derive::de::DeXml #lifetime_param for #name #ty_generics #where_clause {
             fn de_xml(__parser: &mut ::nanoxml::de::XmlParser<'a>) -> Result<Self, ::nanoxml::de::XmlError> {
-                #(#field_init)*
-                while let Ok((__attr_key, __attr_value)) = __parser.attr_or_tag_open_end()? {
-                    match __attr_key {
-                        #(#de_attr)*
-                        _ => return Err(::nanoxml::de::XmlError::InvalidField),
-                    }
+                match __parser.current_tag() {
+                    #(#cases)*
+                    _ => Err(::nanoxml::de::XmlError::InvalidVariant),
                 }
-                #de_body
-                Ok(Self { #(#field_unwraps)* })
+            }
+
+            fn de_xml_field(__parser: &mut ::nanoxml::de::XmlParser<'a>) -> Result<Self, ::nanoxml::de::XmlError> {
+                __parser.tag_open_end()?;
+                let __value = match __parser.tag_open_or_close()? {
+                    Ok(__tag) if <Self as ::nanoxml::derive::de::DeXml>::matches_tag(__tag) => Self::de_xml(__parser)?,
+                    Ok(_) => return Err(::nanoxml::de::XmlError::InvalidVariant),
+                    Err(_) => return Err(::nanoxml::de::XmlError::MissingField),
+                };
+                __parser.tag_close()?;
+                Ok(__value)
+            }
+
+            fn de_xml_field_in_place(&mut self, __parser: &mut ::nanoxml::de::XmlParser<'a>) -> Result<(), ::nanoxml::de::XmlError> {
+                *self = Self::de_xml_field(__parser)?;
+                Ok(())
+            }
+
+            fn matches_tag(tag: &str) -> bool {
+                matches!(tag, #(#tags)|*)
             }
         }
-    };
 
-    let top_level_impl = quote! {
         impl #impl_generics ::nanoxml::derive::de::DeXmlTopLevel #lifetime_param for #name #ty_generics #where_clause {
             const TAG_NAME: &'static str = #rename;
         }
     };
 
+    full_impl.into()
+}
+
+#[cfg(feature = "de")]
+fn derive_dexml_tagged_enum(
+    name: &Ident,
+    rename: &str,
+    tag: &str,
+    variants: &Punctuated<Variant, Comma>,
+    generics: Generics,
+) -> TokenStream {
+    let variants = get_xml_variants(variants);
+
+    let (generics_clone, lifetime_param) = with_de_lifetime(&generics);
+    let (impl_generics, _, _) = generics_clone.split_for_impl();
+    let (_, ty_generics, where_clause) = generics.split_for_impl();
+
+    let cases = variants.iter().map(|v| {
+        let variant_name = v.variant_name;
+        let renamed = &v.renamed;
+        let fields = match v.fields {
+            Fields::Named(fields) => get_xml_fields(&fields.named),
+            Fields::Unit => Vec::new(),
+            Fields::Unnamed(_) => {
+                panic!("tagged enum variants must be unit variants or struct variants")
+            }
+        };
+        let de_fields = de_fields(&fields, quote! { Self::#variant_name }, Some(tag));
+        quote! {
+            if __type == #renamed {
+                #de_fields
+            }
+        }
+    });
+
     let full_impl = quote! {
-        #dexml_impl
-        #top_level_impl
+        impl #lifetime_param ::nanoxml::derive::de::DeXml #lifetime_param for #name #ty_generics #where_clause {
+            fn de_xml(__parser: &mut ::nanoxml::de::XmlParser<'a>) -> Result<Self, ::nanoxml::de::XmlError> {
+                let __type = __parser.find_attr(#tag)?.ok_or(::nanoxml::de::XmlError::MissingField)?;
+                #(#cases else)*
+                {
+                    Err(::nanoxml::de::XmlError::InvalidVariant)
+                }
+            }
+        }
+
+        impl #impl_generics ::nanoxml::derive::de::DeXmlTopLevel #lifetime_param for #name #ty_generics #where_clause {
+            const TAG_NAME: &'static str = #rename;
+        }
     };
 
     full_impl.into()
@@ -342,6 +1045,12 @@ fn derive_dexml_enum(
                 }
             }
         }
+
+        impl<'a> ::nanoxml::derive::de::DeXml<'a> for #name {
+            fn de_xml(parser: &mut ::nanoxml::de::XmlParser<'a>) -> Result<Self, ::nanoxml::de::XmlError> {
+                <Self as ::nanoxml::derive::de::DeXmlAttr>::de_xml_element(parser)
+            }
+        }
     };
 
     let top_level_impl = quote! {
@@ -358,8 +1067,16 @@ fn derive_dexml_enum(
     full_impl.into()
 }
 
-fn get_rename_attr(attrs: &[Attribute]) -> Option<String> {
+struct XmlContainer {
+    rename: Option<String>,
+    unit_element: bool,
+    tag: Option<String>,
+}
+
+fn get_container_attrs(attrs: &[Attribute]) -> XmlContainer {
     let mut renamed = None;
+    let mut unit_element = None;
+    let mut tag = None;
     for attr in attrs.iter().filter(|attr| attr.path().is_ident("nanoxml")) {
         attr.parse_nested_meta(|meta| {
             if meta.path.is_ident("rename") {
@@ -369,6 +1086,24 @@ fn get_rename_attr(attrs: &[Attribute]) -> Option<String> {
                 let value = meta.value().expect("rename requires value");
                 let lit: LitStr = value.parse().expect("rename requires atr value");
                 renamed = Some(lit.value());
+            } else if meta.path.is_ident("unit") {
+                if unit_element.is_some() {
+                    panic!("duplicate unit attr")
+                }
+                let value = meta.value().expect("unit requires value");
+                let lit: LitStr = value.parse().expect("unit requires str value");
+                unit_element = Some(match lit.value().as_str() {
+                    "text" => false,
+                    "element" => true,
+                    _ => panic!("unit must be \"text\" or \"element\""),
+                });
+            } else if meta.path.is_ident("tag") {
+                if tag.is_some() {
+                    panic!("duplicate tag attr")
+                }
+                let value = meta.value().expect("tag requires value");
+                let lit: LitStr = value.parse().expect("tag requires str value");
+                tag = Some(lit.value());
             } else {
                 panic!("invalid nanoxml attr");
             }
@@ -376,7 +1111,14 @@ fn get_rename_attr(attrs: &[Attribute]) -> Option<String> {
         })
         .unwrap();
     }
-    renamed
+    if tag.is_some() && unit_element.is_some() {
+        panic!("unit attr cannot be combined with tag attr");
+    }
+    XmlContainer {
+        rename: renamed,
+        unit_element: unit_element.unwrap_or(false),
+        tag,
+    }
 }
 
 struct XmlField<'a> {
@@ -387,11 +1129,17 @@ struct XmlField<'a> {
     renamed: String,
     skip_ser: bool,
     default_de: Option<Option<String>>,
+    append: bool,
+    map: Option<MapLayout>,
+    key: String,
+    list: bool,
+    wrapper: Option<String>,
 }
 
 struct XmlVariant<'a> {
     variant_name: &'a Ident,
     renamed: String,
+    fields: &'a Fields,
 }
 
 #[derive(Copy, Clone, Eq, PartialEq)]
@@ -406,6 +1154,14 @@ enum FieldType {
     Regular,
     Option,
     Seq,
+    Map,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum MapLayout {
+    Entry,
+    Tag,
+    Attr,
 }
 
 fn get_xml_fields(fields: &Punctuated<Field, Comma>) -> Vec<XmlField<'_>> {
@@ -421,6 +1177,11 @@ fn get_xml_fields(fields: &Punctuated<Field, Comma>) -> Vec<XmlField<'_>> {
         let mut is_text = false;
         let mut skip_ser = false;
         let mut default_de = None;
+        let mut append = None;
+        let mut map = None;
+        let mut key = None;
+        let mut list = false;
+        let mut wrapper = None;
 
         for attr in field
             .attrs
@@ -441,6 +1202,8 @@ fn get_xml_fields(fields: &Punctuated<Field, Comma>) -> Vec<XmlField<'_>> {
                     is_attr = true;
                 } else if meta.path.is_ident("text") {
                     is_text = true;
+                } else if meta.path.is_ident("list") {
+                    list = true;
                 } else if meta.path.is_ident("skip_ser") {
                     skip_ser = true;
                 } else if meta.path.is_ident("default_de") {
@@ -454,6 +1217,43 @@ fn get_xml_fields(fields: &Punctuated<Field, Comma>) -> Vec<XmlField<'_>> {
                         }
                         Err(_) => default_de = Some(None),
                     }
+                } else if meta.path.is_ident("merge") {
+                    if append.is_some() {
+                        panic!("duplicate merge attr")
+                    }
+                    let value = meta.value().expect("merge requires value");
+                    let lit: LitStr = value.parse().expect("merge requires str value");
+                    append = match lit.value().as_str() {
+                        "append" => Some(true),
+                        "replace" => Some(false),
+                        _ => panic!("merge must be \"append\" or \"replace\""),
+                    };
+                } else if meta.path.is_ident("map") {
+                    if map.is_some() {
+                        panic!("duplicate map attr")
+                    }
+                    let value = meta.value().expect("map requires value");
+                    let lit: LitStr = value.parse().expect("map requires str value");
+                    map = match lit.value().as_str() {
+                        "entry" => Some(MapLayout::Entry),
+                        "tag" => Some(MapLayout::Tag),
+                        "attr" => Some(MapLayout::Attr),
+                        _ => panic!("map must be \"entry\", \"tag\" or \"attr\""),
+                    };
+                } else if meta.path.is_ident("wrapper") {
+                    if wrapper.is_some() {
+                        panic!("duplicate wrapper attr")
+                    }
+                    let value = meta.value().expect("wrapper requires value");
+                    let lit: LitStr = value.parse().expect("wrapper requires str value");
+                    wrapper = Some(lit.value());
+                } else if meta.path.is_ident("key") {
+                    if key.is_some() {
+                        panic!("duplicate key attr")
+                    }
+                    let value = meta.value().expect("key requires value");
+                    let lit: LitStr = value.parse().expect("key requires str value");
+                    key = Some(lit.value());
                 } else {
                     panic!("invalid nanoxml attr");
                 }
@@ -462,7 +1262,31 @@ fn get_xml_fields(fields: &Punctuated<Field, Comma>) -> Vec<XmlField<'_>> {
             .unwrap();
         }
 
-        let field_type = if is_seq {
+        if map.is_some() && (is_seq || is_attr || is_text) {
+            panic!("map is incompatible with seq, attr and text");
+        }
+
+        if list && (is_seq || !(is_attr || is_text)) {
+            panic!("list only works for attr and text fields");
+        }
+
+        if key.is_some() && map != Some(MapLayout::Entry) {
+            panic!("key only works for map = \"entry\" fields");
+        }
+
+        if map.is_some_and(|m| m != MapLayout::Entry) && renamed.is_some() {
+            panic!("rename only works for map = \"entry\" fields");
+        }
+
+        if let Some(layout @ (MapLayout::Tag | MapLayout::Attr)) = map
+            && ret.iter().any(|f| f.map == Some(layout))
+        {
+            panic!("only one map = \"tag\" and one map = \"attr\" field is allowed");
+        }
+
+        let field_type = if map.is_some() {
+            FieldType::Map
+        } else if is_seq {
             FieldType::Seq
         } else if is_option(real_type) {
             FieldType::Option
@@ -474,6 +1298,21 @@ fn get_xml_fields(fields: &Punctuated<Field, Comma>) -> Vec<XmlField<'_>> {
             panic!("default_de only works for non-option, non-seq fields");
         }
 
+        if wrapper.is_some() && field_type != FieldType::Seq {
+            panic!("wrapper only works for seq fields");
+        }
+
+        if append.is_some() && field_type != FieldType::Seq {
+            panic!("merge only works for seq fields");
+        }
+
+        if append == Some(true)
+            && matches!(option_inner(real_type).unwrap_or(real_type), Type::Array(_))
+        {
+            panic!("merge = \"append\" does not work for array fields");
+        }
+
+        let is_attr = is_attr || map == Some(MapLayout::Attr);
         let field_kind = match (is_attr, is_text) {
             (true, true) => {
                 panic!("#[attr] and #[text] on the same field are incompatible");
@@ -505,6 +1344,11 @@ fn get_xml_fields(fields: &Punctuated<Field, Comma>) -> Vec<XmlField<'_>> {
             renamed,
             skip_ser,
             default_de,
+            append: append.unwrap_or(false),
+            map,
+            key: key.unwrap_or_else(|| "key".to_string()),
+            list,
+            wrapper,
         });
     }
 
@@ -537,15 +1381,26 @@ fn get_xml_variants(variants: &Punctuated<Variant, Comma>) -> Vec<XmlVariant<'_>
             .unwrap();
         }
 
+        if let Fields::Unnamed(fields) = &variant.fields
+            && fields.unnamed.len() != 1
+        {
+            panic!("tuple enum variants must have exactly one field");
+        }
+
         ret.push(XmlVariant {
             variant_name,
             renamed,
+            fields: &variant.fields,
         });
     }
 
     ret
 }
 
+fn is_element_enum(variants: &Punctuated<Variant, Comma>) -> bool {
+    variants.iter().any(|v| !matches!(v.fields, Fields::Unit))
+}
+
 fn is_option(ty: &Type) -> bool {
     check_type(
         ty,
@@ -553,6 +1408,22 @@ fn is_option(ty: &Type) -> bool {
     )
 }
 
+fn option_inner(ty: &Type) -> Option<&Type> {
+    if !is_option(ty) {
+        return None;
+    }
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let PathArguments::AngleBracketed(args) = &path.path.segments.last()?.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        GenericArgument::Type(inner) => Some(inner),
+        _ => None,
+    }
+}
+
 fn check_type(ty: &Type, valid: &[&str]) -> bool {
     let path = match *ty {
         Type::Path(ref path) if path.qself.is_none() => &path.path,
@@ -1,45 +1,96 @@
 #[cfg(feature = "alloc")]
 extern crate alloc;
 
+use core::cmp::Ordering;
 use core::error::Error;
-use core::fmt::{Display, Formatter, Result as FmtResult};
+use core::fmt::{Display, Formatter, Result as FmtResult, Write};
+use core::hash::{Hash, Hasher};
+use core::ops::Range;
 use core::str::Chars;
 
 #[cfg(feature = "alloc")]
 use alloc::{borrow::Cow, string::String};
 
+use crate::xsd::XsParseError;
+
 pub struct XmlParser<'a> {
     pub(crate) s: &'a str,
     pub(crate) n: usize,
     pub(crate) in_tag: bool,
     pub(crate) selfclose: bool,
+    pub(crate) open_end: bool,
+    pub(crate) token_start: usize,
+    pub(crate) current_tag: XmlSpan,
+    pub(crate) hidden_attr: Option<usize>,
 }
 
 impl<'a> XmlParser<'a> {
     pub fn new(s: &'a str) -> Result<Self, XmlError> {
-        let s = skip_xml_header(s.trim())?;
+        let s = s.trim_end();
+        let n = s.len() - skip_xml_header(s.trim_start())?.len();
         Ok(Self {
             s,
-            n: 0,
+            n,
             in_tag: false,
             selfclose: false,
+            open_end: false,
+            token_start: 0,
+            current_tag: XmlSpan::default(),
+            hidden_attr: None,
         })
     }
 
+    pub fn peek_token(&mut self) -> Result<Option<XmlToken<'a>>, XmlError> {
+        let checkpoint = self.checkpoint();
+        let token = self.next_token();
+        self.restore(checkpoint);
+        token
+    }
+
+    pub fn checkpoint(&self) -> XmlCheckpoint {
+        XmlCheckpoint {
+            n: self.n,
+            in_tag: self.in_tag,
+            selfclose: self.selfclose,
+            open_end: self.open_end,
+            token_start: self.token_start,
+            current_tag: self.current_tag,
+            hidden_attr: self.hidden_attr,
+        }
+    }
+
+    pub fn restore(&mut self, checkpoint: XmlCheckpoint) {
+        self.n = checkpoint.n;
+        self.in_tag = checkpoint.in_tag;
+        self.selfclose = checkpoint.selfclose;
+        self.open_end = checkpoint.open_end;
+        self.token_start = checkpoint.token_start;
+        self.current_tag = checkpoint.current_tag;
+        self.hidden_attr = checkpoint.hidden_attr;
+    }
+
+    pub fn current_tag(&self) -> &'a str {
+        &self.s[self.current_tag.range()]
+    }
+
     pub fn next_token(&mut self) -> Result<Option<XmlToken<'a>>, XmlError> {
+        self.open_end = false;
         if self.selfclose {
             self.selfclose = false;
+            self.token_start = self.n;
             return Ok(Some(XmlToken::TagClose));
         }
 
         self.consume_whitespace();
-        if self.s[self.n..].starts_with("<!--") {
-            let Some(comment_end) = self.s[self.n..].find("-->") else {
+        while self.s.as_bytes()[self.n..].starts_with(b"<!--") {
+            self.n += 4;
+            let Some(comment_end) = find_comment_end(&self.s.as_bytes()[self.n..]) else {
                 return Err(XmlError::UnexpectedEof);
             };
-            self.n += comment_end + 4;
+            self.n += comment_end + 3;
             self.consume_whitespace();
         }
+        self.token_start = self.n;
         match self.consume_ascii() {
             Some(b'<') => {
                 let close = self.opt_consume_ascii(b'/').is_some();
@@ -51,16 +102,19 @@ impl<'a> XmlParser<'a> {
                     }
                     false => {
                         self.in_tag = true;
+                        self.current_tag = XmlSpan::new(self.n - identifier.len(), self.n);
                         XmlToken::TagOpenStart(identifier)
                     }
                 }))
             }
             Some(b'>') => {
                 self.in_tag = false;
+                self.open_end = true;
                 Ok(Some(XmlToken::TagOpenEnd))
             }
             Some(b'/') if self.in_tag => {
                 self.expect_ascii(b'>')?;
+                self.in_tag = false;
                 self.selfclose = true;
                 Ok(Some(XmlToken::TagOpenEnd))
             }
@@ -69,18 +123,41 @@ impl<'a> XmlParser<'a> {
                 let attr_key = self.consume_identifier()?;
                 self.expect_ascii(b'=')?;
                 self.expect_ascii(b'"')?;
-                let attr_value = self.consume_until('"')?;
+                let (attr_value, escaped) = self.consume_until(b'"')?;
                 self.expect_ascii(b'"')?;
-                Ok(Some(XmlToken::Attribute(attr_key, XmlStr::new(attr_value))))
+                if self.hidden_attr == Some(self.token_start) {
+                    return self.next_token();
+                }
+                let attr_value = XmlStr::with_escapes(attr_value, escaped);
+                Ok(Some(XmlToken::Attribute(attr_key, attr_value)))
             }
             Some(_) => {
                 self.n -= 1;
-                Ok(Some(XmlToken::Text(XmlStr::new(self.consume_until('<')?))))
+                let (text, escaped) = self.consume_until(b'<')?;
+                Ok(Some(XmlToken::Text(XmlStr::with_escapes(text, escaped))))
             }
             None => Ok(None),
         }
     }
 
+    pub fn next_token_spanned(&mut self) -> Result<Option<(XmlToken<'a>, XmlTokenSpan)>, XmlError> {
+        let Some(token) = self.next_token()? else {
+            return Ok(None);
+        };
+        let full = XmlSpan::new(self.token_start, self.n);
+        let (name, value) = match token {
+            XmlToken::TagOpenStart(name) => (Some(self.span_of(name)), None),
+            XmlToken::TagOpenEnd => (None, None),
+            XmlToken::TagClose if full.is_empty() => (None, None),
+            XmlToken::TagClose => (Some(XmlSpan::new(full.start + 2, full.end - 1)), None),
+            XmlToken::Attribute(key, value) => {
+                (Some(self.span_of(key)), Some(self.span_of(value.raw())))
+            }
+            XmlToken::Text(text) => (None, Some(self.span_of(text.raw()))),
+        };
+        Ok(Some((token, XmlTokenSpan { full, name, value })))
+    }
+
     pub fn tag_open_start(&mut self, expect: &str) -> Result<(), XmlError> {
         match self.next_token()?.ok_or(XmlError::UnexpectedEof)? {
             XmlToken::TagOpenStart(tag) if tag == expect => Ok(()),
@@ -125,6 +202,19 @@ impl<'a> XmlParser<'a> {
         }
     }
 
+    pub fn find_attr(&mut self, key: &str) -> Result<Option<XmlStr<'a>>, XmlError> {
+        Ok(self.scan_attr(key)?.map(|(_, value)| value))
+    }
+
+    // like `find_attr`, but the attribute is no longer returned when reading the current tag
+    pub fn take_attr(&mut self, key: &str) -> Result<Option<XmlStr<'a>>, XmlError> {
+        let Some((start, value)) = self.scan_attr(key)? else {
+            return Ok(None);
+        };
+        self.hidden_attr = Some(start);
+        Ok(Some(value))
+    }
+
     pub fn tag_open_or_close(&mut self) -> Result<Result<&'a str, ()>, XmlError> {
         match self.next_token()?.ok_or(XmlError::UnexpectedEof)? {
             XmlToken::TagOpenStart(tag) => Ok(Ok(tag)),
@@ -140,7 +230,7 @@ impl<'a> XmlParser<'a> {
                 token = self.next_token()?.ok_or(XmlError::UnexpectedEof)?;
                 s
             }
-            _ => XmlStr::new(""),
+            _ => XmlStr::with_escapes("", false),
         };
         match token {
             XmlToken::TagClose => Ok(s),
@@ -148,6 +238,44 @@ impl<'a> XmlParser<'a> {
         }
     }
 
+    // skips the element just opened, either inside its open tag or right after its `>`
+    pub fn skip_element(&mut self) -> Result<(), XmlError> {
+        if !self.in_tag && !self.selfclose && !self.open_end {
+            return Err(XmlError::UnexpectedToken);
+        }
+        self.skip_depth(1)
+    }
+
+    pub fn skip_to_parent_end(&mut self) -> Result<(), XmlError> {
+        match self.in_tag || self.selfclose {
+            true => self.skip_depth(2),
+            false => self.skip_depth(1),
+        }
+    }
+
+    pub fn outer_xml(&mut self) -> Result<&'a str, XmlError> {
+        if !self.in_tag || self.s.as_bytes()[self.token_start] != b'<' {
+            return Err(XmlError::UnexpectedToken);
+        }
+        let start = self.token_start;
+        self.skip_depth(1)?;
+        Ok(&self.s[start..self.n])
+    }
+
+    pub fn inner_xml(&mut self) -> Result<&'a str, XmlError> {
+        if !self.in_tag {
+            return Err(XmlError::UnexpectedToken);
+        }
+        while self.attr_or_tag_open_end()?.is_ok() {}
+        if self.selfclose {
+            self.tag_close()?;
+            return Ok("");
+        }
+        let start = self.n;
+        self.skip_depth(1)?;
+        Ok(&self.s[start..self.token_start])
+    }
+
     pub fn check_end(&mut self) -> Result<(), XmlError> {
         match self.next_token()? {
             Some(_) => Err(XmlError::TrailingChars),
@@ -155,6 +283,22 @@ impl<'a> XmlParser<'a> {
         }
     }
 
+    fn skip_depth(&mut self, mut depth: usize) -> Result<(), XmlError> {
+        while depth > 0 {
+            match self.next_token()?.ok_or(XmlError::UnexpectedEof)? {
+                XmlToken::TagOpenStart(_) => depth += 1,
+                XmlToken::TagClose => depth -= 1,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn span_of(&self, s: &str) -> XmlSpan {
+        let start = s.as_ptr() as usize - self.s.as_ptr() as usize;
+        XmlSpan::new(start, start + s.len())
+    }
+
     fn consume_ascii(&mut self) -> Option<u8> {
         let c = *self.s.as_bytes()[self.n..].first()?;
         self.n += 1;
@@ -201,18 +345,30 @@ impl<'a> XmlParser<'a> {
         Ok(&self.s[start..self.n])
     }
 
-    fn consume_until(&mut self, expect: char) -> Result<&'a str, XmlError> {
-        let mut i = self.n;
-        while i < self.s.len() {
-            let c = self.s[i..].chars().next().unwrap();
-            if c == expect {
-                let result = &self.s[self.n..i];
-                self.n = i;
-                return Ok(result);
+    fn scan_attr(&mut self, key: &str) -> Result<Option<(usize, XmlStr<'a>)>, XmlError> {
+        let checkpoint = self.checkpoint();
+        let found = (|| {
+            while let Ok((attr_key, attr_value)) = self.attr_or_tag_open_end()? {
+                if attr_key == key {
+                    return Ok(Some((self.token_start, attr_value)));
+                }
             }
-            i += c.len_utf8();
+            Ok(None)
+        })();
+        self.restore(checkpoint);
+        found
+    }
+
+    fn consume_until(&mut self, expect: u8) -> Result<(&'a str, bool), XmlError> {
+        let bytes = &self.s.as_bytes()[self.n..];
+        let mut i = find_byte2(bytes, expect, b'&').ok_or(XmlError::UnexpectedEof)?;
+        let escaped = bytes[i] == b'&';
+        if escaped {
+            i += find_byte(&bytes[i..], expect).ok_or(XmlError::UnexpectedEof)?;
         }
-        Err(XmlError::UnexpectedEof)
+        let result = &self.s[self.n..(self.n + i)];
+        self.n += i;
+        Ok((result, escaped))
     }
 
     fn consume_whitespace(&mut self) {
@@ -227,7 +383,32 @@ impl<'a> XmlParser<'a> {
     }
 }
 
-#[derive(Debug)]
+impl<'a> Iterator for XmlParser<'a> {
+    type Item = Result<XmlToken<'a>, XmlError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.next_token();
+        if token.is_err() {
+            self.n = self.s.len();
+            self.in_tag = false;
+            self.selfclose = false;
+        }
+        token.transpose()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct XmlCheckpoint {
+    n: usize,
+    in_tag: bool,
+    selfclose: bool,
+    open_end: bool,
+    token_start: usize,
+    current_tag: XmlSpan,
+    hidden_attr: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum XmlToken<'a> {
     TagOpenStart(&'a str),
     TagOpenEnd,
@@ -236,9 +417,41 @@ pub enum XmlToken<'a> {
     Text(XmlStr<'a>),
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct XmlSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl XmlSpan {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct XmlTokenSpan {
+    pub full: XmlSpan,
+    pub name: Option<XmlSpan>,
+    pub value: Option<XmlSpan>,
+}
+
+#[derive(Clone, Copy, Debug)]
 pub struct XmlStr<'a> {
     pub(crate) s: &'a str,
+    pub(crate) escaped: bool,
 }
 
 impl<'a> XmlStr<'a> {
@@ -246,32 +459,71 @@ impl<'a> XmlStr<'a> {
         self.s
     }
 
+    pub fn has_escapes(&self) -> bool {
+        self.escaped
+    }
+
     pub fn iter(&self) -> XmlStrIter<'a> {
         XmlStrIter::new(self.s)
     }
 
+    pub fn split_whitespace(&self) -> impl Iterator<Item = XmlStr<'a>> + use<'a> {
+        self.s
+            .split([' ', '\t', '\n', '\r'])
+            .filter(|s| !s.is_empty())
+            .map(XmlStr::new)
+    }
+
     #[cfg(feature = "alloc")]
     pub fn parsed(&self) -> Cow<'a, str> {
+        if !self.escaped {
+            return Cow::Borrowed(self.s);
+        }
         let mut i = 0;
-        while i < self.s.len() {
-            let s = &self.s[i..];
-            let c = s.chars().next().unwrap();
-            if c == '&'
-                && let Some((c, n)) = starts_with_xml_escape_code(&s[1..])
-            {
+        while let Some(amp) = find_byte(&self.s.as_bytes()[i..], b'&') {
+            i += amp;
+            if let Some((c, n)) = starts_with_xml_escape_code(&self.s[(i + 1)..]) {
                 let mut ret = String::from(&self.s[0..i]);
                 ret.push(c);
-                let iter = XmlStrIter::new(&s[(n + 1)..]);
+                let iter = XmlStrIter::new(&self.s[(i + n + 1)..]);
                 for c in iter {
                     ret.push(c);
                 }
                 return Cow::Owned(ret);
             }
-            i += c.len_utf8();
+            i += 1;
         }
         Cow::Borrowed(self.s)
     }
 
+    pub fn decode_into<'b>(&self, buf: &'b mut [u8]) -> Result<&'b str, XmlError> {
+        if !self.escaped {
+            let buf = buf
+                .get_mut(..self.s.len())
+                .ok_or(XmlError::BufferOverflow)?;
+            buf.copy_from_slice(self.s.as_bytes());
+            return Ok(core::str::from_utf8(buf).unwrap());
+        }
+        let mut n = 0;
+        for c in self.iter() {
+            let len = c.len_utf8();
+            let dst = buf.get_mut(n..(n + len)).ok_or(XmlError::BufferOverflow)?;
+            c.encode_utf8(dst);
+            n += len;
+        }
+        Ok(core::str::from_utf8(&buf[..n]).unwrap())
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> FmtResult {
+        if !self.escaped {
+            return writer.write_str(self.s);
+        }
+        for c in self.iter() {
+            writer.write_char(c)?;
+        }
+        Ok(())
+    }
+
     #[cfg(feature = "alloc")]
     pub fn owned(&self) -> String {
         let mut s = String::new();
@@ -290,20 +542,68 @@ impl<'a> XmlStr<'a> {
         Ok(ret)
     }
 
-    fn new(s: &'a str) -> Self {
-        Self { s }
+    pub fn new(s: &'a str) -> Self {
+        Self::with_escapes(s, find_byte(s.as_bytes(), b'&').is_some())
+    }
+
+    pub(crate) fn with_escapes(s: &'a str, escaped: bool) -> Self {
+        Self { s, escaped }
+    }
+}
+
+impl Display for XmlStr<'_> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        self.write_to(f)
+    }
+}
+
+impl PartialEq for XmlStr<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.escaped, other.escaped) {
+            (false, false) => self.s == other.s,
+            _ => self.iter().eq(other.iter()),
+        }
+    }
+}
+
+impl Eq for XmlStr<'_> {}
+
+impl PartialOrd for XmlStr<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for XmlStr<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.escaped, other.escaped) {
+            (false, false) => self.s.cmp(other.s),
+            _ => self.iter().cmp(other.iter()),
+        }
+    }
+}
+
+impl Hash for XmlStr<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for c in self.iter() {
+            c.hash(state);
+        }
+        state.write_u8(0xff);
     }
 }
 
 impl<'a> PartialEq<str> for XmlStr<'a> {
     fn eq(&self, other: &str) -> bool {
-        self.iter().eq(other.chars())
+        match self.escaped {
+            true => self.iter().eq(other.chars()),
+            false => self.s == other,
+        }
     }
 }
 
 impl<'a> PartialEq<&str> for XmlStr<'a> {
     fn eq(&self, other: &&str) -> bool {
-        self.iter().eq(other.chars())
+        *self == **other
     }
 }
 
@@ -351,6 +651,12 @@ pub enum XmlError {
     MissingField,
     SeqOverflow,
     SeqUnderflow,
+    DuplicateItem,
+    AppendUnsupported,
+    MissingValue,
+    BufferOverflow,
+    NeedsOwned,
+    InvalidUtf8,
 }
 
 impl Display for XmlError {
@@ -369,15 +675,27 @@ impl Display for XmlError {
             Self::MissingField => write!(f, "MissingField"),
             Self::SeqOverflow => write!(f, "SeqOverflow"),
             Self::SeqUnderflow => write!(f, "SeqUnderflow"),
+            Self::DuplicateItem => write!(f, "DuplicateItem"),
+            Self::AppendUnsupported => write!(f, "AppendUnsupported"),
+            Self::MissingValue => write!(f, "MissingValue"),
+            Self::BufferOverflow => write!(f, "BufferOverflow"),
+            Self::NeedsOwned => write!(f, "NeedsOwned"),
+            Self::InvalidUtf8 => write!(f, "InvalidUtf8"),
         }
     }
 }
 
 impl Error for XmlError {}
 
+impl From<XsParseError> for XmlError {
+    fn from(_: XsParseError) -> Self {
+        Self::InvalidValue
+    }
+}
+
 fn skip_xml_header(s: &str) -> Result<&str, XmlError> {
     let bytes = s.as_bytes();
-    if &bytes[0..5] != b"<?xml" {
+    if !bytes.starts_with(b"<?xml") {
         return Ok(s);
     }
     let mut n = 5;
@@ -407,7 +725,73 @@ fn starts_with_xml_escape_code(s: &str) -> Option<(char, usize)> {
         Some(('"', 5))
     } else if s.starts_with("apos;") {
         Some(('\'', 5))
+    } else if let Some(s) = s.strip_prefix('#') {
+        let (digits, radix) = match s.strip_prefix('x') {
+            Some(hex) => (hex, 16),
+            None => (s, 10),
+        };
+        let len = digits.bytes().take(8).position(|b| b == b';')?;
+        if !digits[..len].bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let code = u32::from_str_radix(&digits[..len], radix).ok()?;
+        let c = char::from_u32(code)?;
+        Some((c, s.len() - digits.len() + len + 2))
     } else {
         None
     }
 }
+
+const WORD: usize = size_of::<usize>();
+const LO: usize = usize::from_ne_bytes([0x01; WORD]);
+const HI: usize = usize::from_ne_bytes([0x80; WORD]);
+
+// the usual swar trick: a word contains a zero byte iff this is non-zero. since the needles are
+// ascii, a match can never be part of a multi-byte utf-8 sequence, so the returned index is
+// always on a char boundary.
+fn word_has_byte(word: usize, needle: u8) -> bool {
+    let x = word ^ (LO * needle as usize);
+    x.wrapping_sub(LO) & !x & HI != 0
+}
+
+fn find_byte(haystack: &[u8], needle: u8) -> Option<usize> {
+    let mut i = 0;
+    while let Some(chunk) = haystack.get(i..(i + WORD)) {
+        let word = usize::from_ne_bytes(chunk.try_into().unwrap());
+        if word_has_byte(word, needle) {
+            break;
+        }
+        i += WORD;
+    }
+    haystack[i..]
+        .iter()
+        .position(|&b| b == needle)
+        .map(|pos| i + pos)
+}
+
+fn find_byte2(haystack: &[u8], a: u8, b: u8) -> Option<usize> {
+    let mut i = 0;
+    while let Some(chunk) = haystack.get(i..(i + WORD)) {
+        let word = usize::from_ne_bytes(chunk.try_into().unwrap());
+        if word_has_byte(word, a) || word_has_byte(word, b) {
+            break;
+        }
+        i += WORD;
+    }
+    haystack[i..]
+        .iter()
+        .position(|&c| c == a || c == b)
+        .map(|pos| i + pos)
+}
+
+fn find_comment_end(s: &[u8]) -> Option<usize> {
+    let mut i = 0;
+    while let Some(dash) = find_byte(&s[i..], b'-') {
+        i += dash;
+        if s[i..].starts_with(b"-->") {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
@@ -1,116 +1,1056 @@
+use core::borrow::BorrowMut;
+use core::cell::OnceCell;
+use core::marker::PhantomData;
 use core::mem::MaybeUninit;
-use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use core::num::{NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128, NonZeroIsize};
+use core::num::{NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128, NonZeroUsize};
 use core::str::FromStr;
+use core::time::Duration;
 
-use crate::de::{XmlError, XmlParser, XmlStr};
+#[cfg(any(feature = "std", feature = "heapless"))]
+use core::hash::{BuildHasher, Hash};
+
+use crate::de::{XmlError, XmlParser, XmlStr, XmlToken};
+use crate::xsd::{Base64, Hex, XsDate, XsDateTime, XsDuration, XsTime};
+use crate::xsd::{decode_base64, decode_hex};
 
 pub use nanoxml_derive::DeXml;
 
-#[cfg(feature = "alloc")]
-extern crate alloc;
+pub use super::RawXmlRef;
+
+#[cfg(feature = "alloc")]
+pub use super::RawXml;
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::{borrow::Cow, boxed::Box, rc::Rc, string::String, vec::Vec};
+
+#[cfg(feature = "alloc")]
+use alloc::collections::{BTreeMap, BTreeSet, LinkedList, VecDeque};
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+use alloc::sync::Arc;
+
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+
+pub trait DeXml<'a>: Sized + 'a {
+    fn de_xml(parser: &mut XmlParser<'a>) -> Result<Self, XmlError>;
+
+    // not atomic: on error, fields that were already read stay overwritten
+    fn de_xml_in_place(&mut self, parser: &mut XmlParser<'a>) -> Result<(), XmlError> {
+        *self = Self::de_xml(parser)?;
+        Ok(())
+    }
+
+    // called for struct fields, where the element is named after the field
+    fn de_xml_field(parser: &mut XmlParser<'a>) -> Result<Self, XmlError> {
+        Self::de_xml(parser)
+    }
+
+    fn de_xml_field_in_place(&mut self, parser: &mut XmlParser<'a>) -> Result<(), XmlError> {
+        self.de_xml_in_place(parser)
+    }
+
+    fn matches_tag(tag: &str) -> bool {
+        _ = tag;
+        false
+    }
+
+    fn deserialize_element_as(parser: &mut XmlParser<'a>, tag: &str) -> Result<Self, XmlError> {
+        match parser.next_token()?.ok_or(XmlError::UnexpectedEof)? {
+            XmlToken::TagOpenStart(t) if Self::matches_tag(t) => Self::de_xml(parser),
+            XmlToken::TagOpenStart(t) if t == tag => Self::de_xml_field(parser),
+            XmlToken::TagOpenStart(_) => Err(XmlError::NameMismatch),
+            _ => Err(XmlError::UnexpectedToken),
+        }
+    }
+
+    fn deserialize_str_as(s: &'a str, tag: &str) -> Result<Self, XmlError> {
+        let mut parser = XmlParser::new(s)?;
+        let ret = Self::deserialize_element_as(&mut parser, tag)?;
+        parser.check_end()?;
+        Ok(ret)
+    }
+
+    fn deserialize_fragment_as<'t>(
+        s: &'a str,
+        tag: &'t str,
+    ) -> Result<DeXmlIter<'a, 't, Self>, XmlError> {
+        Ok(DeXmlIter::new(XmlParser::new(s)?, tag, false))
+    }
+
+    fn deserialize_children_as<'t, P: BorrowMut<XmlParser<'a>>>(
+        mut parser: P,
+        root: &str,
+        tag: &'t str,
+    ) -> Result<DeXmlIter<'a, 't, Self, P>, XmlError> {
+        let p = parser.borrow_mut();
+        p.tag_open_start(root)?;
+        while p.attr_or_tag_open_end()?.is_ok() {}
+        Ok(DeXmlIter::new(parser, tag, true))
+    }
+}
+
+pub trait DeXmlAttr<'a>: DeXml<'a> {
+    fn de_xml_attr(s: XmlStr<'a>) -> Result<Self, XmlError>;
+
+    fn de_xml_element(parser: &mut XmlParser<'a>) -> Result<Self, XmlError> {
+        parser.tag_open_end()?;
+        let s = parser.text_and_tag_close()?;
+        Self::de_xml_attr(s)
+    }
+}
+
+// attribute types are read from the text of an element. this is not a blanket impl, so that
+// `DeXml` can be forwarded through `Box<T>` for arbitrary `T`. exported so that types with a
+// hand-written `DeXmlAttr` impl can get their `DeXml` impl the same way.
+#[macro_export]
+macro_rules! impl_de_element {
+    ([$($gen:tt)*] $ty:ty) => {
+        impl<$($gen)*> $crate::derive::de::DeXml<'a> for $ty {
+            fn de_xml(
+                parser: &mut $crate::de::XmlParser<'a>,
+            ) -> Result<Self, $crate::de::XmlError> {
+                <Self as $crate::derive::de::DeXmlAttr<'a>>::de_xml_element(parser)
+            }
+        }
+    };
+    ($ty:ty) => {
+        $crate::impl_de_element!(['a] $ty);
+    };
+}
+
+pub trait DeXmlSeq<'a>: Sized + 'a {
+    type Intermediate;
+
+    fn new_seq() -> Self::Intermediate;
+    fn push_item(this: &mut Self::Intermediate, parser: &mut XmlParser<'a>)
+    -> Result<(), XmlError>;
+    fn finish(this: Self::Intermediate) -> Result<Self, XmlError>;
+
+    fn append(this: &mut Self, items: Self::Intermediate) -> Result<(), XmlError> {
+        _ = (this, items);
+        Err(XmlError::AppendUnsupported)
+    }
+
+    fn finish_opt(this: Self::Intermediate) -> Result<Option<Self>, XmlError> {
+        Self::finish(this).map(Some)
+    }
+
+    fn matches_tag(tag: &str) -> bool {
+        _ = tag;
+        false
+    }
+
+    fn push_wrapped(
+        this: &mut Self::Intermediate,
+        parser: &mut XmlParser<'a>,
+        item_tag: &str,
+    ) -> Result<(), XmlError> {
+        while parser.attr_or_tag_open_end()?.is_ok() {}
+        while let Ok(tag) = parser.tag_open_or_close()? {
+            if tag != item_tag && !Self::matches_tag(tag) {
+                return Err(XmlError::InvalidField);
+            }
+            Self::push_item(this, parser)?;
+        }
+        Ok(())
+    }
+}
+
+pub trait DeXmlMap<'a>: Sized + 'a {
+    type Key: DeXmlAttr<'a>;
+    type Value: DeXml<'a>;
+
+    fn new_map() -> Self;
+    fn insert(this: &mut Self, key: Self::Key, value: Self::Value) -> Result<(), XmlError>;
+
+    fn push_entry(
+        this: &mut Self,
+        parser: &mut XmlParser<'a>,
+        key_attr: &str,
+    ) -> Result<(), XmlError> {
+        let key = parser.take_attr(key_attr)?.ok_or(XmlError::MissingField)?;
+        let key = Self::Key::de_xml_attr(key)?;
+        let value = Self::Value::de_xml_field(parser)?;
+        Self::insert(this, key, value)
+    }
+
+    fn push_tag(this: &mut Self, parser: &mut XmlParser<'a>, tag: &'a str) -> Result<(), XmlError> {
+        let key = Self::Key::de_xml_attr(XmlStr::new(tag))?;
+        let value = Self::Value::de_xml_field(parser)?;
+        Self::insert(this, key, value)
+    }
+
+    fn push_attr(this: &mut Self, key: &'a str, value: XmlStr<'a>) -> Result<(), XmlError>
+    where
+        Self::Value: DeXmlAttr<'a>,
+    {
+        let key = Self::Key::de_xml_attr(XmlStr::new(key))?;
+        let value = Self::Value::de_xml_attr(value)?;
+        Self::insert(this, key, value)
+    }
+}
+
+pub trait DeXmlList<'a>: Sized + 'a {
+    fn de_xml_list(s: XmlStr<'a>) -> Result<Self, XmlError>;
+}
+
+pub trait DeXmlTopLevel<'a>: DeXml<'a> {
+    const TAG_NAME: &'static str;
+
+    fn deserialize_str(s: &'a str) -> Result<Self, XmlError> {
+        Self::deserialize_str_as(s, Self::TAG_NAME)
+    }
+
+    fn deserialize_str_in_place(&mut self, s: &'a str) -> Result<(), XmlError> {
+        let mut parser = XmlParser::new(s)?;
+        match parser.next_token()?.ok_or(XmlError::UnexpectedEof)? {
+            XmlToken::TagOpenStart(t) if Self::matches_tag(t) => {
+                self.de_xml_in_place(&mut parser)?
+            }
+            XmlToken::TagOpenStart(t) if t == Self::TAG_NAME => {
+                self.de_xml_field_in_place(&mut parser)?
+            }
+            XmlToken::TagOpenStart(_) => return Err(XmlError::NameMismatch),
+            _ => return Err(XmlError::UnexpectedToken),
+        }
+        parser.check_end()
+    }
+
+    fn deserialize_element(parser: &mut XmlParser<'a>) -> Result<Self, XmlError> {
+        Self::deserialize_element_as(parser, Self::TAG_NAME)
+    }
+
+    fn deserialize_fragment(s: &'a str) -> Result<DeXmlIter<'a, 'static, Self>, XmlError> {
+        Self::deserialize_fragment_as(s, Self::TAG_NAME)
+    }
+
+    fn deserialize_children<P: BorrowMut<XmlParser<'a>>>(
+        parser: P,
+        root: &str,
+    ) -> Result<DeXmlIter<'a, 'static, Self, P>, XmlError> {
+        Self::deserialize_children_as(parser, root, Self::TAG_NAME)
+    }
+}
+
+pub struct DeXmlIter<'a, 't, T, P = XmlParser<'a>> {
+    parser: P,
+    tag: &'t str,
+    nested: bool,
+    skip_unknown: bool,
+    done: bool,
+    _marker: PhantomData<&'a T>,
+}
+
+impl<'a, 't, T: DeXml<'a>, P: BorrowMut<XmlParser<'a>>> DeXmlIter<'a, 't, T, P> {
+    fn new(parser: P, tag: &'t str, nested: bool) -> Self {
+        Self {
+            parser,
+            tag,
+            nested,
+            skip_unknown: nested,
+            done: false,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn skip_unknown(mut self) -> Self {
+        self.skip_unknown = true;
+        self
+    }
+
+    pub fn into_parser(self) -> P {
+        self.parser
+    }
+
+    fn next_item(&mut self) -> Result<Option<T>, XmlError> {
+        let parser = self.parser.borrow_mut();
+        loop {
+            match parser.next_token()? {
+                Some(XmlToken::TagOpenStart(tag)) if T::matches_tag(tag) => {
+                    return T::de_xml(parser).map(Some);
+                }
+                Some(XmlToken::TagOpenStart(tag)) if tag == self.tag => {
+                    return T::de_xml_field(parser).map(Some);
+                }
+                Some(XmlToken::TagOpenStart(_)) if self.skip_unknown => parser.skip_element()?,
+                Some(XmlToken::TagOpenStart(_)) => return Err(XmlError::NameMismatch),
+                Some(XmlToken::TagClose) if self.nested => return Ok(None),
+                None if !self.nested => return Ok(None),
+                Some(_) => return Err(XmlError::UnexpectedToken),
+                None => return Err(XmlError::UnexpectedEof),
+            }
+        }
+    }
+}
+
+impl<'a, T: DeXml<'a>, P: BorrowMut<XmlParser<'a>>> Iterator for DeXmlIter<'a, '_, T, P> {
+    type Item = Result<T, XmlError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let item = self.next_item().transpose();
+        self.done = !matches!(item, Some(Ok(_)));
+        item
+    }
+}
+
+pub trait DeXmlOwned: for<'a> DeXml<'a> {}
+
+impl<T: for<'a> DeXml<'a>> DeXmlOwned for T {}
+
+pub trait DeXmlTopLevelOwned: for<'a> DeXmlTopLevel<'a> {
+    #[cfg(feature = "alloc")]
+    fn deserialize_string(s: String) -> Result<Self, XmlError> {
+        Self::deserialize_str(&s)
+    }
+
+    fn deserialize_bytes(b: &[u8]) -> Result<Self, XmlError> {
+        let s = core::str::from_utf8(b).map_err(|_| XmlError::InvalidUtf8)?;
+        Self::deserialize_str(s)
+    }
+}
+
+impl<T: for<'a> DeXmlTopLevel<'a>> DeXmlTopLevelOwned for T {}
+
+// primitive values are whitespace-collapsed after decoding, as xsd requires. they are short, so
+// once trimmed a small stack buffer is usually enough to decode them without allocating.
+fn with_collapsed<T>(s: XmlStr<'_>, f: impl FnOnce(&str) -> Option<T>) -> Result<T, XmlError> {
+    const WHITESPACE: [char; 4] = [' ', '\t', '\n', '\r'];
+    let s = XmlStr::with_escapes(s.raw().trim_matches(WHITESPACE), s.has_escapes());
+    if !s.has_escapes() {
+        return f(s.raw()).ok_or(XmlError::InvalidValue);
+    }
+    let mut buf = [0; 64];
+    match s.decode_into(&mut buf) {
+        Ok(decoded) => f(decoded.trim_matches(WHITESPACE)).ok_or(XmlError::InvalidValue),
+        #[cfg(feature = "alloc")]
+        Err(_) => f(s.parsed().trim_matches(WHITESPACE)).ok_or(XmlError::InvalidValue),
+        #[cfg(not(feature = "alloc"))]
+        Err(_) => Err(XmlError::InvalidValue),
+    }
+}
+
+macro_rules! impl_de_from_str {
+    ($ty:ty) => {
+        impl DeXmlAttr<'_> for $ty {
+            fn de_xml_attr(s: XmlStr<'_>) -> Result<Self, XmlError> {
+                with_collapsed(s, |s| FromStr::from_str(s).ok())
+            }
+        }
+        impl_de_element!($ty);
+    };
+}
+
+macro_rules! impl_de_float {
+    ($ty:ty) => {
+        impl DeXmlAttr<'_> for $ty {
+            fn de_xml_attr(s: XmlStr<'_>) -> Result<Self, XmlError> {
+                with_collapsed(s, |s| match s {
+                    "INF" | "+INF" => Some(<$ty>::INFINITY),
+                    "-INF" => Some(<$ty>::NEG_INFINITY),
+                    "NaN" => Some(<$ty>::NAN),
+                    _ if s.bytes().all(|b| b"0123456789+-.eE".contains(&b)) => s.parse().ok(),
+                    _ => None,
+                })
+            }
+        }
+        impl_de_element!($ty);
+    };
+}
+
+impl_de_from_str!(i8);
+impl_de_from_str!(i16);
+impl_de_from_str!(i32);
+impl_de_from_str!(i64);
+impl_de_from_str!(i128);
+impl_de_from_str!(isize);
+impl_de_from_str!(u8);
+impl_de_from_str!(u16);
+impl_de_from_str!(u32);
+impl_de_from_str!(u64);
+impl_de_from_str!(u128);
+impl_de_from_str!(usize);
+impl_de_from_str!(NonZeroI8);
+impl_de_from_str!(NonZeroI16);
+impl_de_from_str!(NonZeroI32);
+impl_de_from_str!(NonZeroI64);
+impl_de_from_str!(NonZeroI128);
+impl_de_from_str!(NonZeroIsize);
+impl_de_from_str!(NonZeroU8);
+impl_de_from_str!(NonZeroU16);
+impl_de_from_str!(NonZeroU32);
+impl_de_from_str!(NonZeroU64);
+impl_de_from_str!(NonZeroU128);
+impl_de_from_str!(NonZeroUsize);
+impl_de_float!(f32);
+impl_de_float!(f64);
+impl_de_from_str!(IpAddr);
+impl_de_from_str!(Ipv4Addr);
+impl_de_from_str!(Ipv6Addr);
+impl_de_from_str!(SocketAddr);
+impl_de_from_str!(SocketAddrV4);
+impl_de_from_str!(SocketAddrV6);
+impl_de_from_str!(XsDate);
+impl_de_from_str!(XsTime);
+impl_de_from_str!(XsDateTime);
+impl_de_from_str!(XsDuration);
+
+impl DeXmlAttr<'_> for Duration {
+    fn de_xml_attr(s: XmlStr<'_>) -> Result<Self, XmlError> {
+        Ok(Duration::try_from(XsDuration::de_xml_attr(s)?)?)
+    }
+}
+
+impl_de_element!(Duration);
+
+// a single character is not collapsed, since whitespace is a perfectly valid value here.
+impl DeXmlAttr<'_> for char {
+    fn de_xml_attr(s: XmlStr<'_>) -> Result<Self, XmlError> {
+        let mut buf = [0; 4];
+        let s = match s.has_escapes() {
+            true => s
+                .decode_into(&mut buf)
+                .map_err(|_| XmlError::InvalidValue)?,
+            false => s.raw(),
+        };
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(XmlError::InvalidValue),
+        }
+    }
+}
+
+impl_de_element!(char);
+
+impl DeXmlAttr<'_> for bool {
+    fn de_xml_attr(s: XmlStr<'_>) -> Result<Self, XmlError> {
+        with_collapsed(s, |s| match s {
+            "true" | "1" => Some(true),
+            "false" | "0" => Some(false),
+            _ => None,
+        })
+    }
+}
+
+impl_de_element!(bool);
+
+macro_rules! impl_de_binary {
+    ($wrapper:ident, $decode:ident) => {
+        #[cfg(feature = "alloc")]
+        impl DeXmlAttr<'_> for $wrapper<Vec<u8>> {
+            fn de_xml_attr(s: XmlStr<'_>) -> Result<Self, XmlError> {
+                let mut ret = Vec::new();
+                $decode::<XmlError>(s.iter(), |b| {
+                    ret.push(b);
+                    Ok(())
+                })?;
+                Ok(Self(ret))
+            }
+        }
+
+        #[cfg(feature = "alloc")]
+        impl_de_element!($wrapper<Vec<u8>>);
+
+        #[cfg(feature = "heapless")]
+        impl<const N: usize> DeXmlAttr<'_> for $wrapper<heapless::Vec<u8, N>> {
+            fn de_xml_attr(s: XmlStr<'_>) -> Result<Self, XmlError> {
+                let mut ret = heapless::Vec::new();
+                $decode::<XmlError>(s.iter(), |b| ret.push(b).map_err(|_| XmlError::SeqOverflow))?;
+                Ok(Self(ret))
+            }
+        }
+
+        #[cfg(feature = "heapless")]
+        impl_de_element!(['a, const N: usize] $wrapper<heapless::Vec<u8, N>>);
+
+        impl<const N: usize> DeXmlAttr<'_> for $wrapper<[u8; N]> {
+            fn de_xml_attr(s: XmlStr<'_>) -> Result<Self, XmlError> {
+                let mut ret = [0; N];
+                let mut n = 0;
+                $decode::<XmlError>(s.iter(), |b| {
+                    *ret.get_mut(n).ok_or(XmlError::SeqOverflow)? = b;
+                    n += 1;
+                    Ok(())
+                })?;
+                match n == N {
+                    true => Ok(Self(ret)),
+                    false => Err(XmlError::SeqUnderflow),
+                }
+            }
+        }
+
+        impl_de_element!(['a, const N: usize] $wrapper<[u8; N]>);
+    };
+}
+
+impl_de_binary!(Base64, decode_base64);
+impl_de_binary!(Hex, decode_hex);
+
+impl<'a> DeXmlAttr<'a> for XmlStr<'a> {
+    fn de_xml_attr(s: XmlStr<'a>) -> Result<Self, XmlError> {
+        Ok(s)
+    }
+}
+
+impl_de_element!(XmlStr<'a>);
+
+impl<'a> DeXmlAttr<'a> for &'a str {
+    fn de_xml_attr(s: XmlStr<'a>) -> Result<Self, XmlError> {
+        match s.has_escapes() {
+            true => Err(XmlError::NeedsOwned),
+            false => Ok(s.raw()),
+        }
+    }
+}
+
+impl_de_element!(&'a str);
+
+#[cfg(feature = "alloc")]
+impl<'a> DeXmlAttr<'a> for Cow<'a, str> {
+    fn de_xml_attr(s: XmlStr<'a>) -> Result<Self, XmlError> {
+        Ok(s.parsed())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl_de_element!(Cow<'a, str>);
+
+#[cfg(feature = "alloc")]
+impl DeXmlAttr<'_> for String {
+    fn de_xml_attr(s: XmlStr<'_>) -> Result<Self, XmlError> {
+        Ok(s.owned())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl_de_element!(String);
+
+#[cfg(feature = "alloc")]
+impl DeXmlAttr<'_> for Box<str> {
+    fn de_xml_attr(s: XmlStr<'_>) -> Result<Self, XmlError> {
+        Ok(s.owned().into_boxed_str())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl_de_element!(Box<str>);
+
+#[cfg(feature = "alloc")]
+impl DeXmlAttr<'_> for Rc<str> {
+    fn de_xml_attr(s: XmlStr<'_>) -> Result<Self, XmlError> {
+        Ok(Rc::from(s.parsed().as_ref()))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl_de_element!(Rc<str>);
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl DeXmlAttr<'_> for Arc<str> {
+    fn de_xml_attr(s: XmlStr<'_>) -> Result<Self, XmlError> {
+        Ok(Arc::from(s.parsed().as_ref()))
+    }
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl_de_element!(Arc<str>);
+
+#[cfg(feature = "std")]
+impl DeXmlAttr<'_> for PathBuf {
+    fn de_xml_attr(s: XmlStr<'_>) -> Result<Self, XmlError> {
+        Ok(PathBuf::from(s.owned()))
+    }
+}
+
+#[cfg(feature = "std")]
+impl_de_element!(PathBuf);
+
+#[cfg(feature = "heapless")]
+impl<const N: usize> DeXmlAttr<'_> for heapless::String<N> {
+    fn de_xml_attr(s: XmlStr<'_>) -> Result<Self, XmlError> {
+        s.heapless().map_err(|_| XmlError::InvalidValue)
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl_de_element!(['a, const N: usize] heapless::String<N>);
+
+impl<'a> DeXml<'a> for RawXmlRef<'a> {
+    fn de_xml(parser: &mut XmlParser<'a>) -> Result<Self, XmlError> {
+        Ok(Self(parser.inner_xml()?))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl DeXml<'_> for RawXml {
+    fn de_xml(parser: &mut XmlParser<'_>) -> Result<Self, XmlError> {
+        Ok(Self(String::from(parser.inner_xml()?)))
+    }
+}
+
+#[derive(Debug)]
+pub struct Lazy<'a, T> {
+    raw: Option<&'a str>,
+    field: bool,
+    value: OnceCell<T>,
+}
+
+impl<'a, T: DeXml<'a>> Lazy<'a, T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            raw: None,
+            field: false,
+            value: OnceCell::from(value),
+        }
+    }
+
+    pub fn raw(&self) -> Option<&'a str> {
+        self.raw
+    }
+
+    pub fn is_parsed(&self) -> bool {
+        self.value.get().is_some()
+    }
+
+    // whether `raw` is a field element, which has to be parsed with `DeXml::de_xml_field`
+    #[cfg(feature = "ser")]
+    pub(crate) fn is_field(&self) -> bool {
+        self.field
+    }
+
+    pub fn get(&self) -> Result<&T, XmlError> {
+        if let Some(value) = self.value.get() {
+            return Ok(value);
+        }
+        let value = Self::parse(self.raw.ok_or(XmlError::MissingValue)?, self.field)?;
+        Ok(self.value.get_or_init(|| value))
+    }
+
+    pub fn get_mut(&mut self) -> Result<&mut T, XmlError> {
+        self.get()?;
+        Ok(self.value.get_mut().unwrap())
+    }
+
+    pub fn into_inner(self) -> Result<T, XmlError> {
+        match self.value.into_inner() {
+            Some(value) => Ok(value),
+            None => Self::parse(self.raw.ok_or(XmlError::MissingValue)?, self.field),
+        }
+    }
+
+    fn parse(raw: &'a str, field: bool) -> Result<T, XmlError> {
+        let mut parser = XmlParser::new(raw)?;
+        match parser.next_token()?.ok_or(XmlError::UnexpectedEof)? {
+            XmlToken::TagOpenStart(_) => {}
+            _ => return Err(XmlError::UnexpectedToken),
+        }
+        let ret = match field {
+            true => T::de_xml_field(&mut parser)?,
+            false => T::de_xml(&mut parser)?,
+        };
+        parser.check_end()?;
+        Ok(ret)
+    }
+}
+
+impl<'a, T: DeXml<'a>> DeXml<'a> for Lazy<'a, T> {
+    fn de_xml(parser: &mut XmlParser<'a>) -> Result<Self, XmlError> {
+        Ok(Self {
+            raw: Some(parser.outer_xml()?),
+            field: false,
+            value: OnceCell::new(),
+        })
+    }
+
+    fn de_xml_field(parser: &mut XmlParser<'a>) -> Result<Self, XmlError> {
+        Ok(Self {
+            raw: Some(parser.outer_xml()?),
+            field: true,
+            value: OnceCell::new(),
+        })
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T: DeXml<'a>> DeXml<'a> for Box<T> {
+    fn de_xml(parser: &mut XmlParser<'a>) -> Result<Self, XmlError> {
+        T::de_xml(parser).map(Box::new)
+    }
+
+    fn matches_tag(tag: &str) -> bool {
+        T::matches_tag(tag)
+    }
+
+    fn de_xml_in_place(&mut self, parser: &mut XmlParser<'a>) -> Result<(), XmlError> {
+        T::de_xml_in_place(self, parser)
+    }
+
+    fn de_xml_field(parser: &mut XmlParser<'a>) -> Result<Self, XmlError> {
+        T::de_xml_field(parser).map(Box::new)
+    }
+
+    fn de_xml_field_in_place(&mut self, parser: &mut XmlParser<'a>) -> Result<(), XmlError> {
+        T::de_xml_field_in_place(self, parser)
+    }
+}
+
+// a shared `Rc`/`Arc` cannot be updated in place without `T: Clone`, so it is replaced instead
+#[cfg(feature = "alloc")]
+impl<'a, T: DeXml<'a>> DeXml<'a> for Rc<T> {
+    fn de_xml(parser: &mut XmlParser<'a>) -> Result<Self, XmlError> {
+        T::de_xml(parser).map(Rc::new)
+    }
+
+    fn matches_tag(tag: &str) -> bool {
+        T::matches_tag(tag)
+    }
+
+    fn de_xml_in_place(&mut self, parser: &mut XmlParser<'a>) -> Result<(), XmlError> {
+        match Rc::get_mut(self) {
+            Some(value) => value.de_xml_in_place(parser),
+            None => {
+                *self = Self::de_xml(parser)?;
+                Ok(())
+            }
+        }
+    }
+
+    fn de_xml_field(parser: &mut XmlParser<'a>) -> Result<Self, XmlError> {
+        T::de_xml_field(parser).map(Rc::new)
+    }
+
+    fn de_xml_field_in_place(&mut self, parser: &mut XmlParser<'a>) -> Result<(), XmlError> {
+        match Rc::get_mut(self) {
+            Some(value) => value.de_xml_field_in_place(parser),
+            None => {
+                *self = Self::de_xml_field(parser)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl<'a, T: DeXml<'a>> DeXml<'a> for Arc<T> {
+    fn de_xml(parser: &mut XmlParser<'a>) -> Result<Self, XmlError> {
+        T::de_xml(parser).map(Arc::new)
+    }
+
+    fn matches_tag(tag: &str) -> bool {
+        T::matches_tag(tag)
+    }
+
+    fn de_xml_in_place(&mut self, parser: &mut XmlParser<'a>) -> Result<(), XmlError> {
+        match Arc::get_mut(self) {
+            Some(value) => value.de_xml_in_place(parser),
+            None => {
+                *self = Self::de_xml(parser)?;
+                Ok(())
+            }
+        }
+    }
+
+    fn de_xml_field(parser: &mut XmlParser<'a>) -> Result<Self, XmlError> {
+        T::de_xml_field(parser).map(Arc::new)
+    }
+
+    fn de_xml_field_in_place(&mut self, parser: &mut XmlParser<'a>) -> Result<(), XmlError> {
+        match Arc::get_mut(self) {
+            Some(value) => value.de_xml_field_in_place(parser),
+            None => {
+                *self = Self::de_xml_field(parser)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T: DeXmlAttr<'a>> DeXmlAttr<'a> for Box<T> {
+    fn de_xml_attr(s: XmlStr<'a>) -> Result<Self, XmlError> {
+        T::de_xml_attr(s).map(Box::new)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T: DeXmlAttr<'a>> DeXmlAttr<'a> for Rc<T> {
+    fn de_xml_attr(s: XmlStr<'a>) -> Result<Self, XmlError> {
+        T::de_xml_attr(s).map(Rc::new)
+    }
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl<'a, T: DeXmlAttr<'a>> DeXmlAttr<'a> for Arc<T> {
+    fn de_xml_attr(s: XmlStr<'a>) -> Result<Self, XmlError> {
+        T::de_xml_attr(s).map(Arc::new)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T: DeXmlSeq<'a>> DeXmlSeq<'a> for Box<T> {
+    type Intermediate = T::Intermediate;
+
+    fn matches_tag(tag: &str) -> bool {
+        T::matches_tag(tag)
+    }
+
+    fn new_seq() -> Self::Intermediate {
+        T::new_seq()
+    }
+
+    fn push_item(
+        this: &mut Self::Intermediate,
+        parser: &mut XmlParser<'a>,
+    ) -> Result<(), XmlError> {
+        T::push_item(this, parser)
+    }
+
+    fn finish(this: Self::Intermediate) -> Result<Self, XmlError> {
+        T::finish(this).map(Box::new)
+    }
+
+    fn append(this: &mut Self, items: Self::Intermediate) -> Result<(), XmlError> {
+        T::append(this, items)
+    }
+
+    fn finish_opt(this: Self::Intermediate) -> Result<Option<Self>, XmlError> {
+        T::finish_opt(this).map(|t| t.map(Box::new))
+    }
+}
+
+#[cfg(feature = "alloc")]
+// like `DeXml::de_xml_in_place`, appending only updates an unshared `Rc`/`Arc`
+macro_rules! impl_de_seq_shared {
+    ($ptr:ident) => {
+        impl<'a, T: DeXmlSeq<'a>> DeXmlSeq<'a> for $ptr<T> {
+            type Intermediate = T::Intermediate;
+
+            fn matches_tag(tag: &str) -> bool {
+                T::matches_tag(tag)
+            }
+
+            fn new_seq() -> Self::Intermediate {
+                T::new_seq()
+            }
+
+            fn push_item(
+                this: &mut Self::Intermediate,
+                parser: &mut XmlParser<'a>,
+            ) -> Result<(), XmlError> {
+                T::push_item(this, parser)
+            }
+
+            fn finish(this: Self::Intermediate) -> Result<Self, XmlError> {
+                T::finish(this).map($ptr::new)
+            }
+
+            fn append(this: &mut Self, items: Self::Intermediate) -> Result<(), XmlError> {
+                let this = $ptr::get_mut(this).ok_or(XmlError::AppendUnsupported)?;
+                T::append(this, items)
+            }
+
+            fn finish_opt(this: Self::Intermediate) -> Result<Option<Self>, XmlError> {
+                T::finish_opt(this).map(|t| t.map($ptr::new))
+            }
+        }
+    };
+}
+
+#[cfg(feature = "alloc")]
+impl_de_seq_shared!(Rc);
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl_de_seq_shared!(Arc);
+
+#[cfg(feature = "alloc")]
+impl<'a, T: DeXml<'a>> DeXmlSeq<'a> for Vec<T> {
+    type Intermediate = Self;
+
+    fn matches_tag(tag: &str) -> bool {
+        T::matches_tag(tag)
+    }
 
-#[cfg(feature = "alloc")]
-use alloc::{borrow::Cow, string::String, vec::Vec};
+    fn new_seq() -> Self::Intermediate {
+        Self::new()
+    }
 
-pub trait DeXml<'a>: Sized + 'a {
-    fn de_xml(parser: &mut XmlParser<'a>) -> Result<Self, XmlError>;
-}
+    fn push_item(
+        this: &mut Self::Intermediate,
+        parser: &mut XmlParser<'a>,
+    ) -> Result<(), XmlError> {
+        this.push(T::de_xml(parser)?);
+        Ok(())
+    }
 
-pub trait DeXmlAttr<'a>: Sized + 'a {
-    fn de_xml_attr(s: XmlStr<'a>) -> Result<Self, XmlError>;
-}
+    fn finish(this: Self::Intermediate) -> Result<Self, XmlError> {
+        Ok(this)
+    }
 
-impl<'a, T: DeXmlAttr<'a>> DeXml<'a> for T {
-    fn de_xml(parser: &mut XmlParser<'a>) -> Result<Self, XmlError> {
-        parser.tag_open_end()?;
-        let s = parser.text_and_tag_close()?;
-        Self::de_xml_attr(s)
+    fn append(this: &mut Self, mut items: Self::Intermediate) -> Result<(), XmlError> {
+        this.append(&mut items);
+        Ok(())
     }
 }
 
-pub trait DeXmlSeq<'a>: Sized + 'a {
-    type Intermediate;
+#[cfg(feature = "heapless")]
+impl<'a, T: DeXml<'a>, const N: usize> DeXmlSeq<'a> for heapless::Vec<T, N> {
+    type Intermediate = Self;
 
-    fn new_seq() -> Self::Intermediate;
-    fn push_item(this: &mut Self::Intermediate, parser: &mut XmlParser<'a>)
-    -> Result<(), XmlError>;
-    fn finish(this: Self::Intermediate) -> Result<Self, XmlError>;
+    fn matches_tag(tag: &str) -> bool {
+        T::matches_tag(tag)
+    }
 
-    fn finish_opt(this: Self::Intermediate) -> Result<Option<Self>, XmlError> {
-        Self::finish(this).map(Some)
+    fn new_seq() -> Self::Intermediate {
+        Self::new()
+    }
+
+    fn push_item(
+        this: &mut Self::Intermediate,
+        parser: &mut XmlParser<'a>,
+    ) -> Result<(), XmlError> {
+        this.push(T::de_xml(parser)?)
+            .map_err(|_| XmlError::SeqOverflow)
+    }
+
+    fn finish(this: Self::Intermediate) -> Result<Self, XmlError> {
+        Ok(this)
+    }
+
+    fn append(this: &mut Self, items: Self::Intermediate) -> Result<(), XmlError> {
+        for item in items {
+            this.push(item).map_err(|_| XmlError::SeqOverflow)?;
+        }
+        Ok(())
     }
 }
 
-pub trait DeXmlTopLevel<'a>: DeXml<'a> {
-    const TAG_NAME: &'static str;
+#[cfg(feature = "alloc")]
+impl<'a, T: DeXml<'a>> DeXmlSeq<'a> for Box<[T]> {
+    type Intermediate = Vec<T>;
 
-    fn deserialize_str(s: &'a str) -> Result<Self, XmlError> {
-        let mut parser = XmlParser::new(s)?;
-        parser.tag_open_start(Self::TAG_NAME)?;
-        let ret = Self::de_xml(&mut parser)?;
-        parser.check_end()?;
-        Ok(ret)
+    fn matches_tag(tag: &str) -> bool {
+        T::matches_tag(tag)
+    }
+
+    fn new_seq() -> Self::Intermediate {
+        Vec::new()
+    }
+
+    fn push_item(
+        this: &mut Self::Intermediate,
+        parser: &mut XmlParser<'a>,
+    ) -> Result<(), XmlError> {
+        this.push(T::de_xml(parser)?);
+        Ok(())
+    }
+
+    fn finish(this: Self::Intermediate) -> Result<Self, XmlError> {
+        Ok(this.into_boxed_slice())
+    }
+
+    fn append(this: &mut Self, items: Self::Intermediate) -> Result<(), XmlError> {
+        let mut vec = core::mem::take(this).into_vec();
+        vec.extend(items);
+        *this = vec.into_boxed_slice();
+        Ok(())
     }
 }
 
-macro_rules! impl_de_from_str {
-    ($ty:ty) => {
-        impl DeXmlAttr<'_> for $ty {
-            fn de_xml_attr(s: XmlStr<'_>) -> Result<Self, XmlError> {
-                FromStr::from_str(s.raw()).map_err(|_| XmlError::InvalidValue)
+#[cfg(feature = "alloc")]
+macro_rules! impl_de_seq_list {
+    ($ty:ident) => {
+        impl<'a, T: DeXml<'a>> DeXmlSeq<'a> for $ty<T> {
+            type Intermediate = Self;
+
+            fn matches_tag(tag: &str) -> bool {
+                T::matches_tag(tag)
+            }
+
+            fn new_seq() -> Self::Intermediate {
+                Self::new()
+            }
+
+            fn push_item(
+                this: &mut Self::Intermediate,
+                parser: &mut XmlParser<'a>,
+            ) -> Result<(), XmlError> {
+                this.push_back(T::de_xml(parser)?);
+                Ok(())
+            }
+
+            fn finish(this: Self::Intermediate) -> Result<Self, XmlError> {
+                Ok(this)
+            }
+
+            fn append(this: &mut Self, mut items: Self::Intermediate) -> Result<(), XmlError> {
+                this.append(&mut items);
+                Ok(())
             }
         }
     };
 }
 
-impl_de_from_str!(i8);
-impl_de_from_str!(i16);
-impl_de_from_str!(i32);
-impl_de_from_str!(i64);
-impl_de_from_str!(isize);
-impl_de_from_str!(u8);
-impl_de_from_str!(u16);
-impl_de_from_str!(u32);
-impl_de_from_str!(u64);
-impl_de_from_str!(usize);
-impl_de_from_str!(f32);
-impl_de_from_str!(f64);
-impl_de_from_str!(bool);
-impl_de_from_str!(IpAddr);
-impl_de_from_str!(Ipv4Addr);
-impl_de_from_str!(Ipv6Addr);
+#[cfg(feature = "alloc")]
+impl_de_seq_list!(VecDeque);
 
-impl<'a> DeXmlAttr<'a> for XmlStr<'a> {
-    fn de_xml_attr(s: XmlStr<'a>) -> Result<Self, XmlError> {
-        Ok(s)
+#[cfg(feature = "alloc")]
+impl_de_seq_list!(LinkedList);
+
+#[cfg(feature = "heapless")]
+impl<'a, T: DeXml<'a>, const N: usize> DeXmlSeq<'a> for heapless::Deque<T, N> {
+    type Intermediate = Self;
+
+    fn matches_tag(tag: &str) -> bool {
+        T::matches_tag(tag)
     }
-}
 
-#[cfg(feature = "alloc")]
-impl<'a> DeXmlAttr<'a> for Cow<'a, str> {
-    fn de_xml_attr(s: XmlStr<'a>) -> Result<Self, XmlError> {
-        Ok(s.parsed())
+    fn new_seq() -> Self::Intermediate {
+        Self::new()
     }
-}
 
-#[cfg(feature = "alloc")]
-impl DeXmlAttr<'_> for String {
-    fn de_xml_attr(s: XmlStr<'_>) -> Result<Self, XmlError> {
-        Ok(s.owned())
+    fn push_item(
+        this: &mut Self::Intermediate,
+        parser: &mut XmlParser<'a>,
+    ) -> Result<(), XmlError> {
+        this.push_back(T::de_xml(parser)?)
+            .map_err(|_| XmlError::SeqOverflow)
     }
-}
 
-#[cfg(feature = "heapless")]
-impl<const N: usize> DeXmlAttr<'_> for heapless::String<N> {
-    fn de_xml_attr(s: XmlStr<'_>) -> Result<Self, XmlError> {
-        s.heapless().map_err(|_| XmlError::InvalidValue)
+    fn finish(this: Self::Intermediate) -> Result<Self, XmlError> {
+        Ok(this)
+    }
+
+    fn append(this: &mut Self, items: Self::Intermediate) -> Result<(), XmlError> {
+        for item in items {
+            this.push_back(item).map_err(|_| XmlError::SeqOverflow)?;
+        }
+        Ok(())
     }
 }
 
 #[cfg(feature = "alloc")]
-impl<'a, T: DeXml<'a>> DeXmlSeq<'a> for Vec<T> {
+impl<'a, T: DeXml<'a> + Ord> DeXmlSeq<'a> for BTreeSet<T> {
     type Intermediate = Self;
 
+    fn matches_tag(tag: &str) -> bool {
+        T::matches_tag(tag)
+    }
+
     fn new_seq() -> Self::Intermediate {
         Self::new()
     }
@@ -119,34 +1059,244 @@ impl<'a, T: DeXml<'a>> DeXmlSeq<'a> for Vec<T> {
         this: &mut Self::Intermediate,
         parser: &mut XmlParser<'a>,
     ) -> Result<(), XmlError> {
-        this.push(T::de_xml(parser)?);
-        Ok(())
+        match this.insert(T::de_xml(parser)?) {
+            true => Ok(()),
+            false => Err(XmlError::DuplicateItem),
+        }
     }
 
     fn finish(this: Self::Intermediate) -> Result<Self, XmlError> {
         Ok(this)
     }
+
+    fn append(this: &mut Self, items: Self::Intermediate) -> Result<(), XmlError> {
+        for item in items {
+            if !this.insert(item) {
+                return Err(XmlError::DuplicateItem);
+            }
+        }
+        Ok(())
+    }
 }
 
-#[cfg(feature = "heapless")]
-impl<'a, T: DeXml<'a>, const N: usize> DeXmlSeq<'a> for heapless::Vec<T, N> {
+#[cfg(feature = "std")]
+impl<'a, T, S> DeXmlSeq<'a> for HashSet<T, S>
+where
+    T: DeXml<'a> + Eq + Hash,
+    S: BuildHasher + Default + 'a,
+{
     type Intermediate = Self;
 
+    fn matches_tag(tag: &str) -> bool {
+        T::matches_tag(tag)
+    }
+
     fn new_seq() -> Self::Intermediate {
-        Self::new()
+        Self::default()
     }
 
     fn push_item(
         this: &mut Self::Intermediate,
         parser: &mut XmlParser<'a>,
     ) -> Result<(), XmlError> {
-        this.push(T::de_xml(parser)?)
-            .map_err(|_| XmlError::SeqOverflow)
+        match this.insert(T::de_xml(parser)?) {
+            true => Ok(()),
+            false => Err(XmlError::DuplicateItem),
+        }
     }
 
     fn finish(this: Self::Intermediate) -> Result<Self, XmlError> {
         Ok(this)
     }
+
+    fn append(this: &mut Self, items: Self::Intermediate) -> Result<(), XmlError> {
+        for item in items {
+            if !this.insert(item) {
+                return Err(XmlError::DuplicateItem);
+            }
+        }
+        Ok(())
+    }
+}
+
+// heapless sets cannot be consumed by value, so the items are collected into a vec first.
+#[cfg(feature = "heapless")]
+impl<'a, T, S, const N: usize> DeXmlSeq<'a> for heapless::IndexSet<T, S, N>
+where
+    T: DeXml<'a> + Eq + Hash,
+    S: BuildHasher + Default + 'a,
+{
+    type Intermediate = heapless::Vec<T, N>;
+
+    fn matches_tag(tag: &str) -> bool {
+        T::matches_tag(tag)
+    }
+
+    fn new_seq() -> Self::Intermediate {
+        heapless::Vec::new()
+    }
+
+    fn push_item(
+        this: &mut Self::Intermediate,
+        parser: &mut XmlParser<'a>,
+    ) -> Result<(), XmlError> {
+        let item = T::de_xml(parser)?;
+        if this.contains(&item) {
+            return Err(XmlError::DuplicateItem);
+        }
+        this.push(item).map_err(|_| XmlError::SeqOverflow)
+    }
+
+    fn finish(this: Self::Intermediate) -> Result<Self, XmlError> {
+        let mut ret = Self::default();
+        Self::append(&mut ret, this)?;
+        Ok(ret)
+    }
+
+    fn append(this: &mut Self, items: Self::Intermediate) -> Result<(), XmlError> {
+        for item in items {
+            match this.insert(item) {
+                Ok(true) => {}
+                Ok(false) => return Err(XmlError::DuplicateItem),
+                Err(_) => return Err(XmlError::SeqOverflow),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, K: DeXmlAttr<'a> + Ord, V: DeXml<'a>> DeXmlMap<'a> for BTreeMap<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn new_map() -> Self {
+        Self::new()
+    }
+
+    fn insert(this: &mut Self, key: K, value: V) -> Result<(), XmlError> {
+        match this.insert(key, value) {
+            None => Ok(()),
+            Some(_) => Err(XmlError::DuplicateItem),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, K, V, S> DeXmlMap<'a> for HashMap<K, V, S>
+where
+    K: DeXmlAttr<'a> + Eq + Hash,
+    V: DeXml<'a>,
+    S: BuildHasher + Default + 'a,
+{
+    type Key = K;
+    type Value = V;
+
+    fn new_map() -> Self {
+        Self::default()
+    }
+
+    fn insert(this: &mut Self, key: K, value: V) -> Result<(), XmlError> {
+        match this.insert(key, value) {
+            None => Ok(()),
+            Some(_) => Err(XmlError::DuplicateItem),
+        }
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<'a, K, V, S, const N: usize> DeXmlMap<'a> for heapless::IndexMap<K, V, S, N>
+where
+    K: DeXmlAttr<'a> + Eq + Hash,
+    V: DeXml<'a>,
+    S: BuildHasher + Default + 'a,
+{
+    type Key = K;
+    type Value = V;
+
+    fn new_map() -> Self {
+        Self::default()
+    }
+
+    fn insert(this: &mut Self, key: K, value: V) -> Result<(), XmlError> {
+        match this.insert(key, value) {
+            Ok(None) => Ok(()),
+            Ok(Some(_)) => Err(XmlError::DuplicateItem),
+            Err(_) => Err(XmlError::SeqOverflow),
+        }
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<'a, K: DeXmlAttr<'a> + Eq, V: DeXml<'a>, const N: usize> DeXmlMap<'a>
+    for heapless::LinearMap<K, V, N>
+{
+    type Key = K;
+    type Value = V;
+
+    fn new_map() -> Self {
+        Self::new()
+    }
+
+    fn insert(this: &mut Self, key: K, value: V) -> Result<(), XmlError> {
+        match this.insert(key, value) {
+            Ok(None) => Ok(()),
+            Ok(Some(_)) => Err(XmlError::DuplicateItem),
+            Err(_) => Err(XmlError::SeqOverflow),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T: DeXmlAttr<'a>> DeXmlList<'a> for Vec<T> {
+    fn de_xml_list(s: XmlStr<'a>) -> Result<Self, XmlError> {
+        s.split_whitespace().map(T::de_xml_attr).collect()
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<'a, T: DeXmlAttr<'a>, const N: usize> DeXmlList<'a> for heapless::Vec<T, N> {
+    fn de_xml_list(s: XmlStr<'a>) -> Result<Self, XmlError> {
+        let mut ret = heapless::Vec::new();
+        for item in s.split_whitespace() {
+            ret.push(T::de_xml_attr(item)?)
+                .map_err(|_| XmlError::SeqOverflow)?;
+        }
+        Ok(ret)
+    }
+}
+
+impl<'a, T: DeXmlAttr<'a>, const N: usize> DeXmlList<'a> for [T; N] {
+    fn de_xml_list(s: XmlStr<'a>) -> Result<Self, XmlError> {
+        let mut ret = Self::UNINIT_ARRAY;
+        let mut n = 0;
+        let mut items = s.split_whitespace();
+        let err = loop {
+            let item = match items.next() {
+                Some(item) if n < N => item,
+                Some(_) => break XmlError::SeqOverflow,
+                None if n == N => return Ok(unsafe { MaybeUninit::array_assume_init(ret) }),
+                None => break XmlError::SeqUnderflow,
+            };
+            match T::de_xml_attr(item) {
+                Ok(value) => {
+                    ret[n].write(value);
+                    n += 1;
+                }
+                Err(e) => break e,
+            }
+        };
+        for item in &mut ret[..n] {
+            unsafe { item.assume_init_drop() };
+        }
+        Err(err)
+    }
+}
+
+impl<'a, T: DeXmlList<'a>> DeXmlList<'a> for Option<T> {
+    fn de_xml_list(s: XmlStr<'a>) -> Result<Self, XmlError> {
+        T::de_xml_list(s).map(Some)
+    }
 }
 
 // this stuff is required because rust stoopid
@@ -160,6 +1310,10 @@ impl<T, const N: usize> UninitArray<T, N> for [T; N] {}
 impl<'a, T: DeXml<'a>, const N: usize> DeXmlSeq<'a> for [T; N] {
     type Intermediate = ([MaybeUninit<T>; N], usize);
 
+    fn matches_tag(tag: &str) -> bool {
+        T::matches_tag(tag)
+    }
+
     fn new_seq() -> Self::Intermediate {
         (Self::UNINIT_ARRAY, 0)
     }
@@ -185,6 +1339,17 @@ impl<'a, T: DeXml<'a>, const N: usize> DeXmlSeq<'a> for [T; N] {
         }
     }
 
+    fn append(this: &mut Self, mut items: Self::Intermediate) -> Result<(), XmlError> {
+        _ = this;
+        if items.1 == 0 {
+            return Ok(());
+        }
+        for item in &mut items.0[..items.1] {
+            unsafe { item.assume_init_drop() };
+        }
+        Err(XmlError::AppendUnsupported)
+    }
+
     fn finish_opt(this: Self::Intermediate) -> Result<Option<Self>, XmlError> {
         if this.1 == 0 {
             Ok(None)
@@ -197,6 +1362,10 @@ impl<'a, T: DeXml<'a>, const N: usize> DeXmlSeq<'a> for [T; N] {
 impl<'a, T: DeXmlSeq<'a>> DeXmlSeq<'a> for Option<T> {
     type Intermediate = T::Intermediate;
 
+    fn matches_tag(tag: &str) -> bool {
+        T::matches_tag(tag)
+    }
+
     fn new_seq() -> Self::Intermediate {
         T::new_seq()
     }
@@ -211,4 +1380,14 @@ impl<'a, T: DeXmlSeq<'a>> DeXmlSeq<'a> for Option<T> {
     fn finish(this: Self::Intermediate) -> Result<Self, XmlError> {
         T::finish_opt(this)
     }
+
+    fn append(this: &mut Self, items: Self::Intermediate) -> Result<(), XmlError> {
+        match this {
+            Some(t) => T::append(t, items),
+            None => {
+                *this = T::finish_opt(items)?;
+                Ok(())
+            }
+        }
+    }
 }
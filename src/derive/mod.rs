@@ -3,3 +3,29 @@ pub mod de;
 
 #[cfg(feature = "ser")]
 pub mod ser;
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct RawXml(pub String);
+
+#[cfg(feature = "alloc")]
+impl From<String> for RawXml {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct RawXmlRef<'a>(pub &'a str);
+
+impl<'a> From<&'a str> for RawXmlRef<'a> {
+    fn from(value: &'a str) -> Self {
+        Self(value)
+    }
+}
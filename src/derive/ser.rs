@@ -4,11 +4,24 @@ use core::fmt::Write;
 use core::net::IpAddr;
 use core::net::Ipv4Addr;
 use core::net::Ipv6Addr;
+use core::net::{SocketAddr, SocketAddrV4, SocketAddrV6};
+use core::num::{NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128, NonZeroIsize};
+use core::num::{NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128, NonZeroUsize};
+use core::time::Duration;
 
 #[cfg(feature = "de")]
 use crate::de::XmlStr;
 
+#[cfg(feature = "de")]
+use crate::derive::de::{DeXml, Lazy};
+
 use crate::ser::XmlBuilder;
+use crate::xsd::{Base64, Hex, XsDate, XsDateTime, XsDuration, XsTime};
+
+pub use super::RawXmlRef;
+
+#[cfg(feature = "alloc")]
+pub use super::RawXml;
 
 pub use nanoxml_derive::SerXml;
 
@@ -16,7 +29,22 @@ pub use nanoxml_derive::SerXml;
 extern crate alloc;
 
 #[cfg(feature = "alloc")]
-use alloc::{borrow::Cow, string::String, vec::Vec};
+use alloc::{borrow::Cow, boxed::Box, rc::Rc, string::String, vec::Vec};
+
+#[cfg(feature = "alloc")]
+use alloc::collections::{BTreeMap, BTreeSet, LinkedList, VecDeque};
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+use alloc::sync::Arc;
+
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
 
 pub trait SerXml {
     fn ser_body<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult;
@@ -29,6 +57,10 @@ pub trait SerXml {
         self.ser_body(xml)?;
         xml.tag_close(tag_name)
     }
+
+    fn ser_field<W: Write>(&self, xml: &mut XmlBuilder<'_, W>, tag_name: &str) -> FmtResult {
+        self.ser_xml(xml, tag_name)
+    }
 }
 
 pub trait SerXmlAsAttr: SerXml {
@@ -43,6 +75,61 @@ pub trait SerXmlAsAttr: SerXml {
     }
 }
 
+pub trait SerXmlMap {
+    type Key: SerXmlAsAttr;
+    type Value: SerXml;
+
+    fn entries(&self) -> impl Iterator<Item = (&Self::Key, &Self::Value)>;
+
+    fn ser_map_entries<W: Write>(
+        &self,
+        xml: &mut XmlBuilder<'_, W>,
+        tag_name: &str,
+        key_attr: &str,
+    ) -> FmtResult {
+        for (key, value) in self.entries() {
+            xml.tag_open_start(tag_name)?;
+            key.ser_as_attr(xml, key_attr)?;
+            value.ser_attrs(xml)?;
+            xml.tag_open_end()?;
+            value.ser_body(xml)?;
+            xml.tag_close(tag_name)?;
+        }
+        Ok(())
+    }
+
+    fn ser_map_tags<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult
+    where
+        Self::Key: AsRef<str>,
+    {
+        for (key, value) in self.entries() {
+            value.ser_field(xml, key.as_ref())?;
+        }
+        Ok(())
+    }
+
+    fn ser_map_attrs<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult
+    where
+        Self::Key: AsRef<str>,
+        Self::Value: SerXmlAsAttr,
+    {
+        for (key, value) in self.entries() {
+            value.ser_as_attr(xml, key.as_ref())?;
+        }
+        Ok(())
+    }
+}
+
+pub trait SerXmlList {
+    fn ser_list<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult;
+
+    fn ser_list_as_attr<W: Write>(&self, xml: &mut XmlBuilder<'_, W>, attr_key: &str) -> FmtResult {
+        xml.attr_start(attr_key)?;
+        self.ser_list(xml)?;
+        xml.attr_end()
+    }
+}
+
 pub trait SerXmlTopLevel: SerXml {
     const TAG_NAME: &'static str;
 
@@ -51,12 +138,24 @@ pub trait SerXmlTopLevel: SerXml {
         self.ser_xml(&mut xml, Self::TAG_NAME)
     }
 
+    fn serialize_as<W: Write>(&self, writer: &mut W, tag: &str) -> FmtResult {
+        let mut xml = XmlBuilder::new(writer);
+        self.ser_field(&mut xml, tag)
+    }
+
     #[cfg(feature = "alloc")]
     fn serialize_to_string(&self) -> String {
         let mut s = String::new();
         self.serialize(&mut s).unwrap();
         s
     }
+
+    #[cfg(feature = "alloc")]
+    fn serialize_to_string_as(&self, tag: &str) -> String {
+        let mut s = String::new();
+        self.serialize_as(&mut s, tag).unwrap();
+        s
+    }
 }
 
 macro_rules! impl_ser_primitive {
@@ -78,18 +177,144 @@ impl_ser_primitive!(i8);
 impl_ser_primitive!(i16);
 impl_ser_primitive!(i32);
 impl_ser_primitive!(i64);
+impl_ser_primitive!(i128);
 impl_ser_primitive!(isize);
 impl_ser_primitive!(u8);
 impl_ser_primitive!(u16);
 impl_ser_primitive!(u32);
 impl_ser_primitive!(u64);
+impl_ser_primitive!(u128);
 impl_ser_primitive!(usize);
-impl_ser_primitive!(f32);
-impl_ser_primitive!(f64);
+impl_ser_primitive!(NonZeroI8);
+impl_ser_primitive!(NonZeroI16);
+impl_ser_primitive!(NonZeroI32);
+impl_ser_primitive!(NonZeroI64);
+impl_ser_primitive!(NonZeroI128);
+impl_ser_primitive!(NonZeroIsize);
+impl_ser_primitive!(NonZeroU8);
+impl_ser_primitive!(NonZeroU16);
+impl_ser_primitive!(NonZeroU32);
+impl_ser_primitive!(NonZeroU64);
+impl_ser_primitive!(NonZeroU128);
+impl_ser_primitive!(NonZeroUsize);
 impl_ser_primitive!(bool);
 impl_ser_primitive!(IpAddr);
 impl_ser_primitive!(Ipv4Addr);
 impl_ser_primitive!(Ipv6Addr);
+impl_ser_primitive!(SocketAddr);
+impl_ser_primitive!(SocketAddrV4);
+impl_ser_primitive!(SocketAddrV6);
+impl_ser_primitive!(XsDate);
+impl_ser_primitive!(XsTime);
+impl_ser_primitive!(XsDateTime);
+impl_ser_primitive!(XsDuration);
+
+macro_rules! impl_ser_float {
+    ($ty:ty) => {
+        impl SerXml for $ty {
+            fn ser_body<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+                match *self {
+                    <$ty>::INFINITY => xml.write_str("INF"),
+                    <$ty>::NEG_INFINITY => xml.write_str("-INF"),
+                    f if f.is_nan() => xml.write_str("NaN"),
+                    f => write!(CanonicalFloat::new(&mut xml.writer), "{:E}", f),
+                }
+            }
+            fn ser_attrs<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+                _ = xml;
+                Ok(())
+            }
+        }
+        impl SerXmlAsAttr for $ty {}
+    };
+}
+
+impl_ser_float!(f32);
+impl_ser_float!(f64);
+
+// xsd's canonical float form always has a fractional part in the mantissa (`1.0E0`, not `1E0`)
+struct CanonicalFloat<'w, W> {
+    writer: &'w mut W,
+    has_dot: bool,
+}
+
+impl<'w, W: Write> CanonicalFloat<'w, W> {
+    fn new(writer: &'w mut W) -> Self {
+        Self {
+            writer,
+            has_dot: false,
+        }
+    }
+}
+
+impl<W: Write> Write for CanonicalFloat<'_, W> {
+    fn write_str(&mut self, s: &str) -> FmtResult {
+        match s.find('E') {
+            Some(e) if !self.has_dot => {
+                self.has_dot = true;
+                self.writer.write_str(&s[..e])?;
+                self.writer.write_str(".0")?;
+                self.writer.write_str(&s[e..])
+            }
+            _ => {
+                self.has_dot |= s.contains('.');
+                self.writer.write_str(s)
+            }
+        }
+    }
+}
+
+impl SerXml for Duration {
+    fn ser_body<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+        XsDuration::from(*self).ser_body(xml)
+    }
+
+    fn ser_attrs<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+        _ = xml;
+        Ok(())
+    }
+}
+
+impl SerXmlAsAttr for Duration {}
+
+impl SerXml for char {
+    fn ser_body<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+        xml.text(self.encode_utf8(&mut [0; 4]))
+    }
+
+    fn ser_attrs<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+        _ = xml;
+        Ok(())
+    }
+}
+
+impl SerXmlAsAttr for char {}
+
+impl<T: AsRef<[u8]>> SerXml for Base64<T> {
+    fn ser_body<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+        self.write_to(xml)
+    }
+
+    fn ser_attrs<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+        _ = xml;
+        Ok(())
+    }
+}
+
+impl<T: AsRef<[u8]>> SerXmlAsAttr for Base64<T> {}
+
+impl<T: AsRef<[u8]>> SerXml for Hex<T> {
+    fn ser_body<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+        self.write_to(xml)
+    }
+
+    fn ser_attrs<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+        _ = xml;
+        Ok(())
+    }
+}
+
+impl<T: AsRef<[u8]>> SerXmlAsAttr for Hex<T> {}
 
 macro_rules! impl_ser_str {
     ($ty:ty) => {
@@ -111,6 +336,28 @@ impl_ser_str!(str);
 #[cfg(feature = "alloc")]
 impl_ser_str!(String);
 
+#[cfg(feature = "std")]
+macro_rules! impl_ser_path {
+    ($ty:ty) => {
+        impl SerXml for $ty {
+            fn ser_body<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+                xml.text(self.to_str().ok_or(FmtError)?)
+            }
+            fn ser_attrs<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+                _ = xml;
+                Ok(())
+            }
+        }
+        impl SerXmlAsAttr for $ty {}
+    };
+}
+
+#[cfg(feature = "std")]
+impl_ser_path!(Path);
+
+#[cfg(feature = "std")]
+impl_ser_path!(PathBuf);
+
 #[cfg(feature = "alloc")]
 impl<'a> SerXml for Cow<'a, str> {
     fn ser_body<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
@@ -141,6 +388,54 @@ impl<'a> SerXml for XmlStr<'a> {
 #[cfg(feature = "de")]
 impl<'a> SerXmlAsAttr for XmlStr<'a> {}
 
+#[cfg(feature = "de")]
+impl<'a, T: DeXml<'a> + SerXml> SerXml for Lazy<'a, T> {
+    fn ser_body<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+        self.get().map_err(|_| FmtError)?.ser_body(xml)
+    }
+
+    fn ser_attrs<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+        self.get().map_err(|_| FmtError)?.ser_attrs(xml)
+    }
+
+    fn ser_xml<W: Write>(&self, xml: &mut XmlBuilder<'_, W>, tag_name: &str) -> FmtResult {
+        match self.raw() {
+            Some(raw) if !self.is_parsed() && !self.is_field() => ser_raw_as(xml, raw, tag_name),
+            _ => self.get().map_err(|_| FmtError)?.ser_xml(xml, tag_name),
+        }
+    }
+
+    fn ser_field<W: Write>(&self, xml: &mut XmlBuilder<'_, W>, tag_name: &str) -> FmtResult {
+        match self.raw() {
+            Some(raw) if !self.is_parsed() && self.is_field() => ser_raw_as(xml, raw, tag_name),
+            _ => self.get().map_err(|_| FmtError)?.ser_field(xml, tag_name),
+        }
+    }
+}
+
+// re-emits an unparsed element verbatim, only swapping its tag name if it differs
+#[cfg(feature = "de")]
+fn ser_raw_as<W: Write>(xml: &mut XmlBuilder<'_, W>, raw: &str, tag_name: &str) -> FmtResult {
+    let name_len = raw[1..]
+        .find(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>')
+        .ok_or(FmtError)?;
+    let (name, rest) = raw[1..].split_at(name_len);
+    if name == tag_name {
+        return xml.write_str(raw);
+    }
+    xml.write_str("<")?;
+    xml.write_str(tag_name)?;
+    match rest.rfind("</") {
+        Some(close) if !rest.ends_with("/>") => {
+            xml.write_str(&rest[..close])?;
+            xml.write_str("</")?;
+            xml.write_str(tag_name)?;
+            xml.write_str(">")
+        }
+        _ => xml.write_str(rest),
+    }
+}
+
 impl<T: SerXml> SerXml for Option<T> {
     fn ser_body<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
         match self {
@@ -162,6 +457,13 @@ impl<T: SerXml> SerXml for Option<T> {
             None => Ok(()),
         }
     }
+
+    fn ser_field<W: Write>(&self, xml: &mut XmlBuilder<'_, W>, tag_name: &str) -> FmtResult {
+        match self {
+            Some(t) => t.ser_field(xml, tag_name),
+            None => Ok(()),
+        }
+    }
 }
 
 impl<T: SerXmlAsAttr> SerXmlAsAttr for Option<T> {
@@ -178,8 +480,11 @@ impl<T: SerXmlAsAttr> SerXmlAsAttr for Option<T> {
 }
 
 macro_rules! impl_ser_iter {
-    ($ty:ty) => {
-        impl<T: SerXml> SerXml for $ty {
+    ([$($gen:tt)*] $ty:ty) => {
+        impl<$($gen)*> SerXml for $ty
+        where
+            T: SerXml,
+        {
             fn ser_body<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
                 _ = xml;
                 Err(FmtError)
@@ -196,22 +501,80 @@ macro_rules! impl_ser_iter {
             }
         }
     };
+    ($ty:ty) => {
+        impl_ser_iter!([T] $ty);
+    };
 }
 
-macro_rules! impl_ser_iter_n {
-    ($ty:ty) => {
-        impl<T: SerXml, const N: usize> SerXml for $ty {
-            fn ser_body<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
-                _ = xml;
-                Err(FmtError)
-            }
-            fn ser_attrs<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
-                _ = xml;
-                Err(FmtError)
+impl_ser_iter!([T][T]);
+impl_ser_iter!([T, const N: usize] [T; N]);
+
+#[cfg(feature = "alloc")]
+impl_ser_iter!(Vec<T>);
+
+#[cfg(feature = "alloc")]
+impl_ser_iter!(VecDeque<T>);
+
+#[cfg(feature = "alloc")]
+impl_ser_iter!(LinkedList<T>);
+
+#[cfg(feature = "alloc")]
+impl_ser_iter!(BTreeSet<T>);
+
+#[cfg(feature = "std")]
+impl_ser_iter!([T, S] HashSet<T, S>);
+
+#[cfg(feature = "heapless")]
+impl_ser_iter!([T, const N: usize] heapless::Vec<T, N>);
+
+#[cfg(feature = "heapless")]
+impl_ser_iter!([T, const N: usize] heapless::Deque<T, N>);
+
+#[cfg(feature = "heapless")]
+impl_ser_iter!([T, S, const N: usize] heapless::IndexSet<T, S, N>);
+
+#[cfg(any(feature = "alloc", feature = "heapless"))]
+macro_rules! impl_ser_map {
+    ([$($gen:tt)*] $ty:ty) => {
+        impl<$($gen)*> SerXmlMap for $ty
+        where
+            K: SerXmlAsAttr,
+            V: SerXml,
+        {
+            type Key = K;
+            type Value = V;
+
+            fn entries(&self) -> impl Iterator<Item = (&K, &V)> {
+                self.iter()
             }
-            fn ser_xml<W: Write>(&self, xml: &mut XmlBuilder<'_, W>, tag_name: &str) -> FmtResult {
-                for item in self.iter() {
-                    item.ser_xml(xml, tag_name)?;
+        }
+    };
+}
+
+#[cfg(feature = "alloc")]
+impl_ser_map!([K, V] BTreeMap<K, V>);
+
+#[cfg(feature = "std")]
+impl_ser_map!([K, V, S] HashMap<K, V, S>);
+
+#[cfg(feature = "heapless")]
+impl_ser_map!([K, V, S, const N: usize] heapless::IndexMap<K, V, S, N>);
+
+#[cfg(feature = "heapless")]
+impl_ser_map!([K: Eq, V, const N: usize] heapless::LinearMap<K, V, N>);
+
+macro_rules! impl_ser_list {
+    ([$($gen:tt)*] $ty:ty) => {
+        impl<$($gen)*> SerXmlList for $ty
+        where
+            T: SerXmlAsAttr,
+        {
+            fn ser_list<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+                for (i, item) in self.iter().enumerate() {
+                    if i != 0 {
+                        xml.write_char(' ')?;
+                    }
+                    item.ser_body(xml)?;
                 }
                 Ok(())
             }
@@ -219,14 +582,30 @@ macro_rules! impl_ser_iter_n {
     };
 }
 
-impl_ser_iter!([T]);
-impl_ser_iter_n!([T; N]);
+impl_ser_list!([T][T]);
+impl_ser_list!([T, const N: usize] [T; N]);
 
 #[cfg(feature = "alloc")]
-impl_ser_iter!(Vec<T>);
+impl_ser_list!([T] Vec<T>);
 
 #[cfg(feature = "heapless")]
-impl_ser_iter_n!(heapless::Vec<T, N>);
+impl_ser_list!([T, const N: usize] heapless::Vec<T, N>);
+
+impl<T: SerXmlList> SerXmlList for Option<T> {
+    fn ser_list<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+        match self {
+            Some(t) => t.ser_list(xml),
+            None => Ok(()),
+        }
+    }
+
+    fn ser_list_as_attr<W: Write>(&self, xml: &mut XmlBuilder<'_, W>, attr_key: &str) -> FmtResult {
+        match self {
+            Some(t) => t.ser_list_as_attr(xml, attr_key),
+            None => Ok(()),
+        }
+    }
+}
 
 #[cfg(feature = "heapless")]
 impl<const N: usize> SerXml for heapless::String<N> {
@@ -243,28 +622,56 @@ impl<const N: usize> SerXml for heapless::String<N> {
 #[cfg(feature = "heapless")]
 impl<const N: usize> SerXmlAsAttr for heapless::String<N> {}
 
-impl<T: SerXml + ?Sized> SerXml for &T {
-    fn ser_attrs<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
-        T::ser_attrs(*self, xml)
-    }
+macro_rules! impl_ser_deref {
+    ($ty:ty) => {
+        impl<T: SerXml + ?Sized> SerXml for $ty {
+            fn ser_attrs<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+                T::ser_attrs(self, xml)
+            }
 
-    fn ser_body<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
-        T::ser_body(*self, xml)
-    }
+            fn ser_body<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+                T::ser_body(self, xml)
+            }
+
+            fn ser_xml<W: Write>(&self, xml: &mut XmlBuilder<'_, W>, tag_name: &str) -> FmtResult {
+                T::ser_xml(self, xml, tag_name)
+            }
+
+            fn ser_field<W: Write>(
+                &self,
+                xml: &mut XmlBuilder<'_, W>,
+                tag_name: &str,
+            ) -> FmtResult {
+                T::ser_field(self, xml, tag_name)
+            }
+        }
+
+        impl<T: SerXmlAsAttr + ?Sized> SerXmlAsAttr for $ty {
+            fn ser_as_attr<W: Write>(
+                &self,
+                xml: &mut XmlBuilder<'_, W>,
+                attr_key: &str,
+            ) -> FmtResult {
+                T::ser_as_attr(self, xml, attr_key)
+            }
+
+            fn ser_as_text<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+                T::ser_as_text(self, xml)
+            }
+        }
+    };
 }
 
-impl<T: SerXmlAsAttr + ?Sized> SerXmlAsAttr for &T {}
+impl_ser_deref!(&T);
 
 #[cfg(feature = "alloc")]
-#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
-pub struct RawXml(pub String);
+impl_ser_deref!(Box<T>);
 
 #[cfg(feature = "alloc")]
-impl From<String> for RawXml {
-    fn from(value: String) -> Self {
-        Self(value)
-    }
-}
+impl_ser_deref!(Rc<T>);
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl_ser_deref!(Arc<T>);
 
 #[cfg(feature = "alloc")]
 impl SerXml for RawXml {
@@ -279,3 +686,15 @@ impl SerXml for RawXml {
 
 #[cfg(feature = "alloc")]
 impl SerXmlAsAttr for RawXml {}
+
+impl SerXml for RawXmlRef<'_> {
+    fn ser_body<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+        xml.write_str(self.0)
+    }
+    fn ser_attrs<W: Write>(&self, xml: &mut XmlBuilder<'_, W>) -> FmtResult {
+        _ = xml;
+        Ok(())
+    }
+}
+
+impl SerXmlAsAttr for RawXmlRef<'_> {}
@@ -12,3 +12,5 @@ pub mod derive;
 
 #[cfg(feature = "ser")]
 pub mod ser;
+
+pub mod xsd;
@@ -0,0 +1,623 @@
+use core::error::Error;
+use core::fmt::{Display, Formatter, Result as FmtResult, Write};
+use core::str::FromStr;
+use core::time::Duration;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct XsParseError;
+
+impl Display for XsParseError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "XsParseError")
+    }
+}
+
+impl Error for XsParseError {}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct XsDate {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+    pub timezone: Option<i16>,
+}
+
+impl FromStr for XsDate {
+    type Err = XsParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Parser(s.as_bytes());
+        let (year, month, day) = p.date()?;
+        let timezone = p.timezone()?;
+        p.end()?;
+        Ok(Self {
+            year,
+            month,
+            day,
+            timezone,
+        })
+    }
+}
+
+impl Display for XsDate {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write_date(f, self.year, self.month, self.day)?;
+        write_timezone(f, self.timezone)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct XsTime {
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub nanosecond: u32,
+    pub timezone: Option<i16>,
+}
+
+impl FromStr for XsTime {
+    type Err = XsParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Parser(s.as_bytes());
+        let (hour, minute, second, nanosecond) = p.time()?;
+        let timezone = p.timezone()?;
+        p.end()?;
+        Ok(Self {
+            hour: hour % 24,
+            minute,
+            second,
+            nanosecond,
+            timezone,
+        })
+    }
+}
+
+impl Display for XsTime {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write_time(f, self.hour, self.minute, self.second, self.nanosecond)?;
+        write_timezone(f, self.timezone)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct XsDateTime {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub nanosecond: u32,
+    pub timezone: Option<i16>,
+}
+
+impl XsDateTime {
+    pub fn date(&self) -> XsDate {
+        XsDate {
+            year: self.year,
+            month: self.month,
+            day: self.day,
+            timezone: self.timezone,
+        }
+    }
+
+    pub fn time(&self) -> XsTime {
+        XsTime {
+            hour: self.hour,
+            minute: self.minute,
+            second: self.second,
+            nanosecond: self.nanosecond,
+            timezone: self.timezone,
+        }
+    }
+
+    pub fn to_utc(&self) -> Self {
+        match self.timezone {
+            Some(offset) => self.with_offset_minutes(-(offset as i64), Some(0)),
+            None => *self,
+        }
+    }
+
+    pub fn to_timezone(&self, timezone: i16) -> Self {
+        let utc = self.to_utc();
+        utc.with_offset_minutes(timezone as i64, Some(timezone))
+    }
+
+    fn with_offset_minutes(&self, offset: i64, timezone: Option<i16>) -> Self {
+        let minutes = self.hour as i64 * 60 + self.minute as i64 + offset;
+        let days = days_from_civil(self.year as i64, self.month, self.day);
+        let (year, month, day) = civil_from_days(days + minutes.div_euclid(24 * 60));
+        let minutes = minutes.rem_euclid(24 * 60);
+        Self {
+            year: year as i32,
+            month,
+            day,
+            hour: (minutes / 60) as u8,
+            minute: (minutes % 60) as u8,
+            timezone,
+            ..*self
+        }
+    }
+}
+
+impl FromStr for XsDateTime {
+    type Err = XsParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Parser(s.as_bytes());
+        let (year, month, day) = p.date()?;
+        p.expect(b'T')?;
+        let (hour, minute, second, nanosecond) = p.time()?;
+        let timezone = p.timezone()?;
+        p.end()?;
+        let ret = Self {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            nanosecond,
+            timezone,
+        };
+        match hour {
+            24 => Ok(ret.with_offset_minutes(0, timezone)),
+            _ => Ok(ret),
+        }
+    }
+}
+
+impl Display for XsDateTime {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write_date(f, self.year, self.month, self.day)?;
+        f.write_str("T")?;
+        write_time(f, self.hour, self.minute, self.second, self.nanosecond)?;
+        write_timezone(f, self.timezone)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct XsDuration {
+    pub negative: bool,
+    pub years: u64,
+    pub months: u64,
+    pub days: u64,
+    pub hours: u64,
+    pub minutes: u64,
+    pub seconds: u64,
+    pub nanoseconds: u32,
+}
+
+impl XsDuration {
+    pub fn is_zero(&self) -> bool {
+        Self {
+            negative: false,
+            ..*self
+        } == Self::default()
+    }
+}
+
+impl FromStr for XsDuration {
+    type Err = XsParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Parser(s.as_bytes());
+        let mut ret = Self {
+            negative: p.opt(b'-'),
+            ..Self::default()
+        };
+        p.expect(b'P')?;
+        let mut any = false;
+        let mut in_time = false;
+        let mut order = 0;
+        while !p.0.is_empty() {
+            if !in_time && p.opt(b'T') {
+                in_time = true;
+                any = false;
+                order = 3;
+                continue;
+            }
+            let value = p.number()?;
+            let fraction = match p.opt(b'.') {
+                true => Some(p.fraction()?),
+                false => None,
+            };
+            let unit = match (in_time, p.next()?) {
+                (false, b'Y') => 0,
+                (false, b'M') => 1,
+                (false, b'D') => 2,
+                (true, b'H') => 3,
+                (true, b'M') => 4,
+                (true, b'S') => 5,
+                _ => return Err(XsParseError),
+            };
+            if unit < order || (fraction.is_some() && unit != 5) {
+                return Err(XsParseError);
+            }
+            order = unit + 1;
+            any = true;
+            match unit {
+                0 => ret.years = value,
+                1 => ret.months = value,
+                2 => ret.days = value,
+                3 => ret.hours = value,
+                4 => ret.minutes = value,
+                _ => (ret.seconds, ret.nanoseconds) = (value, fraction.unwrap_or(0)),
+            }
+        }
+        match any {
+            true => Ok(ret),
+            false => Err(XsParseError),
+        }
+    }
+}
+
+impl Display for XsDuration {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        if self.is_zero() {
+            return f.write_str("PT0S");
+        }
+        if self.negative {
+            f.write_str("-")?;
+        }
+        f.write_str("P")?;
+        for (value, unit) in [(self.years, 'Y'), (self.months, 'M'), (self.days, 'D')] {
+            if value != 0 {
+                write!(f, "{value}{unit}")?;
+            }
+        }
+        if self.hours == 0 && self.minutes == 0 && self.seconds == 0 && self.nanoseconds == 0 {
+            return Ok(());
+        }
+        f.write_str("T")?;
+        for (value, unit) in [(self.hours, 'H'), (self.minutes, 'M')] {
+            if value != 0 {
+                write!(f, "{value}{unit}")?;
+            }
+        }
+        if self.seconds != 0 || self.nanoseconds != 0 {
+            write!(f, "{}", self.seconds)?;
+            write_fraction(f, self.nanoseconds)?;
+            f.write_str("S")?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<XsDuration> for Duration {
+    type Error = XsParseError;
+
+    fn try_from(value: XsDuration) -> Result<Self, Self::Error> {
+        if value.is_zero() {
+            return Ok(Duration::ZERO);
+        }
+        if value.negative || value.years != 0 || value.months != 0 {
+            return Err(XsParseError);
+        }
+        let seconds = [
+            (value.days, 86400),
+            (value.hours, 3600),
+            (value.minutes, 60),
+        ]
+        .into_iter()
+        .try_fold(value.seconds, |acc, (v, factor)| {
+            v.checked_mul(factor)?.checked_add(acc)
+        })
+        .ok_or(XsParseError)?;
+        Ok(Duration::new(seconds, value.nanoseconds))
+    }
+}
+
+impl From<Duration> for XsDuration {
+    fn from(value: Duration) -> Self {
+        let seconds = value.as_secs();
+        Self {
+            days: seconds / 86400,
+            hours: seconds % 86400 / 3600,
+            minutes: seconds % 3600 / 60,
+            seconds: seconds % 60,
+            nanoseconds: value.subsec_nanos(),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Base64<T>(pub T);
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Hex<T>(pub T);
+
+impl<T: AsRef<[u8]>> Base64<T> {
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> FmtResult {
+        for chunk in self.0.as_ref().chunks(3) {
+            let n = chunk
+                .iter()
+                .enumerate()
+                .fold(0, |acc, (i, &b)| acc | (b as usize) << (16 - 8 * i));
+            let mut out = [b'='; 4];
+            for (i, c) in out.iter_mut().take(chunk.len() + 1).enumerate() {
+                *c = BASE64_CHARS[(n >> (18 - 6 * i)) & 63];
+            }
+            writer.write_str(core::str::from_utf8(&out).unwrap())?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: AsRef<[u8]>> Hex<T> {
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> FmtResult {
+        for &b in self.0.as_ref() {
+            let out = [HEX_CHARS[(b >> 4) as usize], HEX_CHARS[(b & 15) as usize]];
+            writer.write_str(core::str::from_utf8(&out).unwrap())?;
+        }
+        Ok(())
+    }
+}
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const HEX_CHARS: &[u8; 16] = b"0123456789ABCDEF";
+
+pub fn decode_base64<E: From<XsParseError>>(
+    chars: impl Iterator<Item = char>,
+    mut push: impl FnMut(u8) -> Result<(), E>,
+) -> Result<(), E> {
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut count = 0;
+    let mut padding = 0;
+    for c in chars.filter(|c| !is_xml_whitespace(*c)) {
+        count += 1;
+        if c == '=' {
+            padding += 1;
+            continue;
+        }
+        let value = BASE64_CHARS.iter().position(|&b| b as char == c);
+        let value = value.filter(|_| padding == 0).ok_or(XsParseError)?;
+        acc = (acc << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            push((acc >> bits) as u8)?;
+            acc &= (1 << bits) - 1;
+        }
+    }
+    match count % 4 == 0 && padding <= 2 && acc == 0 {
+        true => Ok(()),
+        false => Err(XsParseError.into()),
+    }
+}
+
+pub fn decode_hex<E: From<XsParseError>>(
+    chars: impl Iterator<Item = char>,
+    mut push: impl FnMut(u8) -> Result<(), E>,
+) -> Result<(), E> {
+    let mut high = None;
+    for c in chars.filter(|c| !is_xml_whitespace(*c)) {
+        let value = c.to_digit(16).ok_or(XsParseError)? as u8;
+        match high.take() {
+            Some(high) => push(high << 4 | value)?,
+            None => high = Some(value),
+        }
+    }
+    match high {
+        None => Ok(()),
+        Some(_) => Err(XsParseError.into()),
+    }
+}
+
+fn is_xml_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\r')
+}
+
+struct Parser<'a>(&'a [u8]);
+
+impl Parser<'_> {
+    fn next(&mut self) -> Result<u8, XsParseError> {
+        let (&c, rest) = self.0.split_first().ok_or(XsParseError)?;
+        self.0 = rest;
+        Ok(c)
+    }
+
+    fn opt(&mut self, expect: u8) -> bool {
+        match self.0.first() {
+            Some(&c) if c == expect => {
+                self.0 = &self.0[1..];
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, expect: u8) -> Result<(), XsParseError> {
+        match self.opt(expect) {
+            true => Ok(()),
+            false => Err(XsParseError),
+        }
+    }
+
+    fn end(&self) -> Result<(), XsParseError> {
+        match self.0.is_empty() {
+            true => Ok(()),
+            false => Err(XsParseError),
+        }
+    }
+
+    fn digits(&mut self) -> &[u8] {
+        let len = self.0.iter().take_while(|c| c.is_ascii_digit()).count();
+        let (digits, rest) = self.0.split_at(len);
+        self.0 = rest;
+        digits
+    }
+
+    fn number(&mut self) -> Result<u64, XsParseError> {
+        let digits = self.digits();
+        if digits.is_empty() {
+            return Err(XsParseError);
+        }
+        digits
+            .iter()
+            .try_fold(0u64, |acc, &c| {
+                acc.checked_mul(10)?.checked_add((c - b'0') as u64)
+            })
+            .ok_or(XsParseError)
+    }
+
+    fn fixed(&mut self, len: usize, max: u8) -> Result<u8, XsParseError> {
+        let digits = self.digits();
+        if digits.len() != len {
+            return Err(XsParseError);
+        }
+        let value = digits.iter().fold(0, |acc, &c| acc * 10 + (c - b'0'));
+        match value <= max {
+            true => Ok(value),
+            false => Err(XsParseError),
+        }
+    }
+
+    // digits beyond nanosecond precision are accepted, but ignored
+    fn fraction(&mut self) -> Result<u32, XsParseError> {
+        let digits = self.digits();
+        if digits.is_empty() {
+            return Err(XsParseError);
+        }
+        let mut nanos = 0;
+        for i in 0..9 {
+            nanos = nanos * 10 + digits.get(i).map_or(0, |c| (c - b'0') as u32);
+        }
+        Ok(nanos)
+    }
+
+    fn date(&mut self) -> Result<(i32, u8, u8), XsParseError> {
+        let negative = self.opt(b'-');
+        let digits = self.digits();
+        if digits.len() < 4 || (digits.len() > 4 && digits[0] == b'0') {
+            return Err(XsParseError);
+        }
+        let year = digits.iter().try_fold(0i32, |acc, &c| {
+            acc.checked_mul(10)?.checked_add((c - b'0') as i32)
+        });
+        let year = year.ok_or(XsParseError)?;
+        let year = if negative { -year } else { year };
+        self.expect(b'-')?;
+        let month = self.fixed(2, 12)?;
+        self.expect(b'-')?;
+        let day = self.fixed(2, 31)?;
+        if month == 0 || day == 0 || day > days_in_month(year as i64, month) {
+            return Err(XsParseError);
+        }
+        Ok((year, month, day))
+    }
+
+    fn time(&mut self) -> Result<(u8, u8, u8, u32), XsParseError> {
+        let hour = self.fixed(2, 24)?;
+        self.expect(b':')?;
+        let minute = self.fixed(2, 59)?;
+        self.expect(b':')?;
+        let second = self.fixed(2, 59)?;
+        let nanosecond = match self.opt(b'.') {
+            true => self.fraction()?,
+            false => 0,
+        };
+        if hour == 24 && (minute != 0 || second != 0 || nanosecond != 0) {
+            return Err(XsParseError);
+        }
+        Ok((hour, minute, second, nanosecond))
+    }
+
+    fn timezone(&mut self) -> Result<Option<i16>, XsParseError> {
+        let sign = match self.0.first() {
+            None => return Ok(None),
+            Some(b'Z') => {
+                self.0 = &self.0[1..];
+                return Ok(Some(0));
+            }
+            Some(b'+') => 1,
+            Some(b'-') => -1,
+            Some(_) => return Err(XsParseError),
+        };
+        self.0 = &self.0[1..];
+        let hours = self.fixed(2, 14)?;
+        self.expect(b':')?;
+        let minutes = self.fixed(2, 59)?;
+        if hours == 14 && minutes != 0 {
+            return Err(XsParseError);
+        }
+        Ok(Some(sign * (hours as i16 * 60 + minutes as i16)))
+    }
+}
+
+fn write_date(f: &mut Formatter, year: i32, month: u8, day: u8) -> FmtResult {
+    if year < 0 {
+        f.write_str("-")?;
+    }
+    write!(f, "{:04}-{:02}-{:02}", year.unsigned_abs(), month, day)
+}
+
+fn write_time(f: &mut Formatter, hour: u8, minute: u8, second: u8, nanos: u32) -> FmtResult {
+    write!(f, "{hour:02}:{minute:02}:{second:02}")?;
+    write_fraction(f, nanos)
+}
+
+fn write_fraction(f: &mut Formatter, nanos: u32) -> FmtResult {
+    if nanos == 0 {
+        return Ok(());
+    }
+    let mut digits = 9;
+    let mut nanos = nanos;
+    while nanos.is_multiple_of(10) {
+        nanos /= 10;
+        digits -= 1;
+    }
+    write!(f, ".{nanos:0digits$}")
+}
+
+fn write_timezone(f: &mut Formatter, timezone: Option<i16>) -> FmtResult {
+    match timezone {
+        None => Ok(()),
+        Some(0) => f.write_str("Z"),
+        Some(offset) => {
+            let sign = if offset < 0 { '-' } else { '+' };
+            let offset = offset.unsigned_abs();
+            write!(f, "{sign}{:02}:{:02}", offset / 60, offset % 60)
+        }
+    }
+}
+
+fn days_in_month(year: i64, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// https://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let doy = (153 * ((month as i64 + 9) % 12) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
@@ -1,6 +1,6 @@
 use std::borrow::Cow;
 
-use nanoxml::de::XmlParser;
+use nanoxml::de::{XmlParser, XmlSpan, XmlStr, XmlToken};
 
 #[test]
 fn de() {
@@ -50,3 +50,266 @@ fn de() {
     assert_eq!(body.raw(), "Ħ€lłöWø®lð");
     assert!(matches!(body.parsed(), Cow::Borrowed("Ħ€lłöWø®lð")));
 }
+
+#[test]
+fn de_lookahead() {
+    let xml = r#"<list><item id="1"/>text<item>2</item></list>"#;
+
+    let mut xml = XmlParser::new(xml).unwrap();
+    xml.tag_open_start("list").unwrap();
+    xml.tag_open_end().unwrap();
+
+    let checkpoint = xml.checkpoint();
+    assert_eq!(
+        xml.peek_token().unwrap(),
+        Some(XmlToken::TagOpenStart("item"))
+    );
+    assert_eq!(
+        xml.peek_token().unwrap(),
+        Some(XmlToken::TagOpenStart("item"))
+    );
+    xml.tag_open_start("item").unwrap();
+    assert!(matches!(
+        xml.peek_token().unwrap(),
+        Some(XmlToken::Attribute("id", _))
+    ));
+    xml.restore(checkpoint);
+    xml.tag_open_start("item").unwrap();
+    let (key, value) = xml.attr().unwrap();
+    assert_eq!(key, "id");
+    assert_eq!(value, "1");
+
+    let rest: Result<Vec<_>, _> = xml.collect();
+    let rest = rest.unwrap();
+    assert_eq!(rest.len(), 8);
+    assert_eq!(rest[0], XmlToken::TagOpenEnd);
+    assert_eq!(rest[1], XmlToken::TagClose);
+    assert!(matches!(rest[2], XmlToken::Text(s) if s == "text"));
+    assert_eq!(rest[7], XmlToken::TagClose);
+
+    let mut xml = XmlParser::new("<a b=\"1").unwrap();
+    assert!(xml.by_ref().any(|t| t.is_err()));
+    assert!(xml.next().is_none());
+}
+
+#[test]
+fn de_skip() {
+    let xml = r#"
+        <root>
+          <skipped a="1"><nested><deep/></nested>text<empty/></skipped>
+          <selfclosed b="2"/>
+          <kept>yes</kept>
+          <rest><x/><y>z</y></rest>
+        </root>
+    "#;
+
+    let mut xml = XmlParser::new(xml).unwrap();
+    xml.tag_open_start("root").unwrap();
+    xml.tag_open_end().unwrap();
+    xml.tag_open_start("skipped").unwrap();
+    xml.skip_element().unwrap();
+    xml.tag_open_start("selfclosed").unwrap();
+    xml.skip_element().unwrap();
+    xml.tag_open_start("kept").unwrap();
+    xml.tag_open_end().unwrap();
+    assert_eq!(xml.text_and_tag_close().unwrap(), "yes");
+    xml.tag_open_start("rest").unwrap();
+    xml.tag_open_end().unwrap();
+    xml.tag_open_start("x").unwrap();
+    xml.skip_to_parent_end().unwrap();
+    assert!(xml.skip_element().is_err());
+    xml.skip_to_parent_end().unwrap();
+    xml.check_end().unwrap();
+}
+
+#[test]
+fn de_skip_after_open_end() {
+    let xml = r#"<root><skipped a="1"><nested/>text</skipped><kept/></root>"#;
+
+    let mut xml = XmlParser::new(xml).unwrap();
+    xml.tag_open_start("root").unwrap();
+    xml.tag_open_end().unwrap();
+    xml.tag_open_start("skipped").unwrap();
+    assert_eq!(
+        xml.attr_or_tag_open_end().unwrap(),
+        Ok(("a", XmlStr::new("1")))
+    );
+    assert_eq!(xml.attr_or_tag_open_end().unwrap(), Err(()));
+    xml.skip_element().unwrap();
+    assert!(xml.skip_element().is_err());
+    xml.tag_open_start("kept").unwrap();
+    xml.skip_element().unwrap();
+    xml.tag_close().unwrap();
+    xml.check_end().unwrap();
+}
+
+#[test]
+fn de_raw_slices() {
+    let xml = r#"<root><a x="1"> <b>t&amp;t</b> </a><c/><d y="2"/></root>"#;
+
+    let mut xml = XmlParser::new(xml).unwrap();
+    xml.tag_open_start("root").unwrap();
+    xml.tag_open_end().unwrap();
+    xml.tag_open_start("a").unwrap();
+    assert_eq!(xml.outer_xml().unwrap(), r#"<a x="1"> <b>t&amp;t</b> </a>"#);
+    xml.tag_open_start("c").unwrap();
+    assert_eq!(xml.outer_xml().unwrap(), "<c/>");
+    xml.tag_open_start("d").unwrap();
+    assert_eq!(xml.inner_xml().unwrap(), "");
+    assert!(xml.inner_xml().is_err());
+    assert_eq!(xml.tag_open_or_close().unwrap(), Err(()));
+    xml.check_end().unwrap();
+
+    let mut xml = XmlParser::new(r#"<a x="1"> <b>t&amp;t</b> </a>"#).unwrap();
+    xml.tag_open_start("a").unwrap();
+    assert_eq!(xml.inner_xml().unwrap(), " <b>t&amp;t</b> ");
+    xml.check_end().unwrap();
+}
+
+#[test]
+fn de_spans() {
+    let src = "  <?xml version=\"1.0\"?>\n<a key=\"v&amp;l\"> text <b/></a>\n";
+
+    let mut xml = XmlParser::new(src).unwrap();
+    let mut spans = Vec::new();
+    while let Some((token, span)) = xml.next_token_spanned().unwrap() {
+        spans.push((token, span));
+    }
+    let slice = |span: XmlSpan| &src[span.range()];
+    let slices: Vec<_> = spans
+        .iter()
+        .map(|(_, span)| {
+            (
+                slice(span.full),
+                span.name.map(slice),
+                span.value.map(slice),
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        slices,
+        [
+            ("<a", Some("a"), None),
+            ("key=\"v&amp;l\"", Some("key"), Some("v&amp;l")),
+            (">", None, None),
+            ("text ", None, Some("text ")),
+            ("<b", Some("b"), None),
+            ("/>", None, None),
+            ("", None, None),
+            ("</a>", Some("a"), None),
+        ]
+    );
+    assert_eq!(spans[0].1.full, XmlSpan::new(24, 26));
+    assert!(matches!(spans[3].0, XmlToken::Text(s) if s == "text "));
+}
+
+#[test]
+fn de_comments() {
+    let xml = "<!-- a --><!----><a>  <!-- b -- c -->  <b>x-y&lt;</b><!-- d --></a>";
+
+    let mut xml = XmlParser::new(xml).unwrap();
+    xml.tag_open_start("a").unwrap();
+    xml.tag_open_end().unwrap();
+    xml.tag_open_start("b").unwrap();
+    xml.tag_open_end().unwrap();
+    assert_eq!(xml.text_and_tag_close().unwrap(), "x-y<");
+    xml.tag_close().unwrap();
+    xml.check_end().unwrap();
+
+    let mut xml = XmlParser::new("<a><!-- unterminated -></a>").unwrap();
+    xml.tag_open_start("a").unwrap();
+    xml.tag_open_end().unwrap();
+    assert!(xml.tag_close().is_err());
+}
+
+#[test]
+fn de_escapes() {
+    let xml = r#"<a plain="abc" escaped="&lt;&gt;&amp;" bogus="&#xD800;&#+5;&#;">t&amp;xt</a>"#;
+
+    let mut xml = XmlParser::new(xml).unwrap();
+    xml.tag_open_start("a").unwrap();
+    let (_, plain) = xml.attr().unwrap();
+    let (_, escaped) = xml.attr().unwrap();
+    let (_, bogus) = xml.attr().unwrap();
+    xml.tag_open_end().unwrap();
+    let text = xml.text_and_tag_close().unwrap();
+
+    assert!(!plain.has_escapes());
+    assert_eq!(plain, "abc");
+    assert!(matches!(plain.parsed(), Cow::Borrowed("abc")));
+    assert!(escaped.has_escapes());
+    assert_eq!(escaped, "<>&");
+    assert!(matches!(escaped.parsed(), Cow::Owned(s) if s == "<>&"));
+    assert!(bogus.has_escapes());
+    assert_eq!(bogus, "&#xD800;&#+5;&#;");
+    assert!(matches!(bogus.parsed(), Cow::Borrowed("&#xD800;&#+5;&#;")));
+    assert_eq!(text, "t&xt");
+    assert_eq!(text, XmlStr::new("t&amp;xt"));
+    assert!(!XmlStr::new("täxt").has_escapes());
+}
+
+#[test]
+fn de_numeric_escapes() {
+    let xml = r#"<a escaped="&#49;&#x32;&amp;">t&#228;xt</a>"#;
+
+    let mut xml = XmlParser::new(xml).unwrap();
+    xml.tag_open_start("a").unwrap();
+    let (_, escaped) = xml.attr().unwrap();
+    xml.tag_open_end().unwrap();
+    let text = xml.text_and_tag_close().unwrap();
+
+    assert!(escaped.has_escapes());
+    assert_eq!(escaped, "12&");
+    assert!(matches!(escaped.parsed(), Cow::Owned(s) if s == "12&"));
+    assert_eq!(text, "täxt");
+    assert_eq!(text, XmlStr::new("t&#228;xt"));
+}
+
+#[test]
+fn de_decode_no_alloc() {
+    use std::collections::HashSet;
+    use std::fmt::Write;
+
+    let plain = XmlStr::new("a<b");
+    let escaped = XmlStr::new("a&lt;b");
+    let other = XmlStr::new("a&lt;c");
+
+    let mut buf = [0u8; 8];
+    assert_eq!(escaped.decode_into(&mut buf).unwrap(), "a<b");
+    assert_eq!(plain.decode_into(&mut buf).unwrap(), "a<b");
+    assert!(escaped.decode_into(&mut buf[..2]).is_err());
+    assert!(plain.decode_into(&mut buf[..2]).is_err());
+
+    let mut s = String::new();
+    escaped.write_to(&mut s).unwrap();
+    write!(s, "|{other}").unwrap();
+    assert_eq!(s, "a<b|a<c");
+
+    assert_eq!(plain, escaped);
+    assert!(escaped < other);
+    assert!(plain < other);
+    let set: HashSet<_> = [plain, escaped, other].into_iter().collect();
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn de_take_attr() {
+    let mut xml = XmlParser::new(r#"<a x="1" key="k" y="2"/>"#).unwrap();
+    xml.tag_open_start("a").unwrap();
+    assert_eq!(xml.find_attr("y").unwrap().unwrap(), "2");
+    assert_eq!(xml.take_attr("key").unwrap().unwrap(), "k");
+    assert!(xml.take_attr("z").unwrap().is_none());
+    assert_eq!(xml.attr().unwrap().0, "x");
+    assert_eq!(xml.attr().unwrap().0, "y");
+    xml.tag_open_end().unwrap();
+    xml.tag_close().unwrap();
+}
+
+#[test]
+fn de_find_attr_error_restores() {
+    let mut xml = XmlParser::new(r#"<a x="1" y=2/>"#).unwrap();
+    xml.tag_open_start("a").unwrap();
+    assert!(xml.find_attr("z").is_err());
+    assert_eq!(xml.attr().unwrap(), ("x", XmlStr::new("1")));
+}
@@ -1,11 +1,15 @@
 #![allow(unused)]
 
 use std::borrow::Cow;
-use std::net::Ipv4Addr;
+use std::collections::{BTreeMap, BTreeSet, LinkedList, VecDeque};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::num::NonZeroU16;
+use std::rc::Rc;
+use std::time::Duration;
 
-use nanoxml::de::XmlStr;
-use nanoxml::derive::de::{DeXml, DeXmlTopLevel};
-use nanoxml::derive::ser::{RawXml, SerXml, SerXmlTopLevel};
+use nanoxml::de::{XmlError, XmlParser, XmlStr};
+use nanoxml::derive::de::{DeXml, DeXmlAttr, DeXmlOwned, DeXmlTopLevel, DeXmlTopLevelOwned, Lazy};
+use nanoxml::derive::ser::{RawXml, RawXmlRef, SerXml, SerXmlTopLevel};
 
 #[derive(Debug, DeXml, PartialEq, SerXml)]
 #[nanoxml(rename = "user")]
@@ -54,6 +58,13 @@ struct Lifetimed<'a> {
     cow: Cow<'a, str>,
 }
 
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct Borrowed<'a> {
+    #[nanoxml(attr)]
+    key: &'a str,
+    value: &'a str,
+}
+
 #[derive(Debug, PartialEq, SerXml)]
 struct SerOnly {
     text: String,
@@ -94,6 +105,22 @@ fn derive() {
     let xml_reconstructed = lifetimed.serialize_to_string();
     assert_eq!(lifetimed_xml, xml_reconstructed);
 
+    let borrowed_xml = "<Borrowed key=\"k\"><value>v</value></Borrowed>";
+    let borrowed = Borrowed::deserialize_str(borrowed_xml).unwrap();
+    assert_eq!(
+        borrowed,
+        Borrowed {
+            key: "k",
+            value: "v"
+        }
+    );
+    assert_eq!(borrowed.serialize_to_string(), borrowed_xml);
+    let escaped_xml = "<Borrowed key=\"k\"><value>&lt;v&gt;</value></Borrowed>";
+    assert!(matches!(
+        Borrowed::deserialize_str(escaped_xml),
+        Err(XmlError::NeedsOwned)
+    ));
+
     let ser_only = SerOnly {
         text: String::from("<Foo>Bar&Baz</Foo>"),
         raw: String::from("<Foo>Bar&Baz</Foo>").into(),
@@ -105,6 +132,945 @@ fn derive() {
     );
 }
 
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct Opaque<'a> {
+    borrowed: RawXmlRef<'a>,
+    owned: RawXml,
+}
+
+#[test]
+fn derive_raw() {
+    let xml = "<Opaque><borrowed> <a x=\"1\">Foo&amp;<b/></a> </borrowed><owned><c></c>text</owned></Opaque>";
+    let opaque = Opaque::deserialize_str(xml).unwrap();
+    assert_eq!(opaque.borrowed.0, " <a x=\"1\">Foo&amp;<b/></a> ");
+    assert_eq!(opaque.owned.0, "<c></c>text");
+    assert_eq!(opaque.serialize_to_string(), xml);
+}
+
+fn load_in_thread<T: DeXmlTopLevelOwned + Send + 'static>(xml: String) -> T {
+    std::thread::spawn(move || T::deserialize_string(xml).unwrap())
+        .join()
+        .unwrap()
+}
+
+fn assert_owned<T: DeXmlOwned>() {}
+
+#[test]
+fn derive_owned() {
+    assert_owned::<Id>();
+    assert_owned::<Role>();
+
+    let id: Id = load_in_thread(String::from("<Id>42</Id>"));
+    assert_eq!(id, Id { id: 42 });
+    assert_eq!(
+        Role::deserialize_bytes(b"<Role>mod</Role>").unwrap(),
+        Role::Moderator
+    );
+    assert!(matches!(
+        Role::deserialize_bytes(b"<Role>\xff</Role>"),
+        Err(XmlError::InvalidUtf8)
+    ));
+}
+
+#[test]
+fn derive_runtime_tags() {
+    let id = Id::deserialize_str_as("<ident>7</ident>", "ident").unwrap();
+    assert_eq!(id, Id { id: 7 });
+    assert_eq!(id.serialize_to_string_as("ident"), "<ident>7</ident>");
+    assert!(Id::deserialize_str("<ident>7</ident>").is_err());
+
+    let mut parser = XmlParser::new("<outer><Id>1</Id><Id>2</Id></outer>").unwrap();
+    parser.tag_open_start("outer").unwrap();
+    parser.tag_open_end().unwrap();
+    assert_eq!(Id::deserialize_element(&mut parser).unwrap(), Id { id: 1 });
+    assert_eq!(Id::deserialize_element(&mut parser).unwrap(), Id { id: 2 });
+    parser.tag_close().unwrap();
+    parser.check_end().unwrap();
+
+    let fragment = "<Id>1</Id> <other/> <Id>2</Id><Id>3</Id>";
+    let ids: Vec<_> = Id::deserialize_fragment(fragment)
+        .unwrap()
+        .skip_unknown()
+        .map(|id| id.unwrap().id)
+        .collect();
+    assert_eq!(ids, [1, 2, 3]);
+    let mut strict = Id::deserialize_fragment(fragment).unwrap();
+    assert!(strict.next().unwrap().is_ok());
+    assert!(matches!(strict.next(), Some(Err(XmlError::NameMismatch))));
+    assert!(strict.next().is_none());
+    let numbers: Vec<_> = u8::deserialize_fragment_as("<n>1</n><n>2</n>", "n")
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(numbers, [1, 2]);
+    let mut broken = Id::deserialize_fragment("<Id>1</Id><Id>x</Id><Id>3</Id>").unwrap();
+    assert!(broken.next().unwrap().is_ok());
+    assert!(broken.next().unwrap().is_err());
+    assert!(broken.next().is_none());
+}
+
+#[test]
+fn derive_children() {
+    let mut xml = String::from("<records count=\"1000\">");
+    for i in 0..1000 {
+        xml.push_str(&format!("<Id>{i}</Id><comment><nested/></comment>"));
+    }
+    xml.push_str("</records>");
+
+    let mut parser = XmlParser::new(&xml).unwrap();
+    let mut sum = 0;
+    for id in Id::deserialize_children(&mut parser, "records").unwrap() {
+        sum += id.unwrap().id;
+    }
+    assert_eq!(sum, 999 * 1000 / 2);
+    parser.check_end().unwrap();
+
+    let parser = XmlParser::new("<records/>").unwrap();
+    assert_eq!(
+        Id::deserialize_children(parser, "records").unwrap().count(),
+        0
+    );
+
+    let parser = XmlParser::new("<r><n>1</n><n>2</n>").unwrap();
+    let mut numbers = u32::deserialize_children_as(parser, "r", "n").unwrap();
+    assert_eq!(numbers.next().unwrap().unwrap(), 1);
+    assert_eq!(numbers.next().unwrap().unwrap(), 2);
+    assert!(matches!(numbers.next(), Some(Err(XmlError::UnexpectedEof))));
+}
+
+#[derive(Debug, DeXml, PartialEq)]
+struct Config {
+    #[nanoxml(attr)]
+    name: String,
+    #[nanoxml(attr)]
+    port: Option<u16>,
+    server: Server,
+    backup: Option<Server>,
+    #[nanoxml(seq)]
+    hosts: Vec<String>,
+    #[nanoxml(seq, merge = "append")]
+    plugins: Vec<String>,
+}
+
+#[derive(Debug, DeXml, PartialEq)]
+struct Server {
+    #[nanoxml(attr)]
+    host: String,
+    #[nanoxml(attr)]
+    threads: u32,
+}
+
+#[test]
+fn derive_in_place() {
+    let mut config = Config {
+        name: String::from("default"),
+        port: Some(80),
+        server: Server {
+            host: String::from("localhost"),
+            threads: 4,
+        },
+        backup: None,
+        hosts: vec![String::from("a"), String::from("b")],
+        plugins: vec![String::from("core")],
+    };
+
+    let site = "<Config port=\"8080\"><server threads=\"16\" host=\"example.org\"/><backup host=\"b\" threads=\"1\"/><plugins>extra</plugins></Config>";
+    config.deserialize_str_in_place(site).unwrap();
+    assert_eq!(config.name, "default");
+    assert_eq!(config.port, Some(8080));
+    assert_eq!(config.server.threads, 16);
+    assert_eq!(
+        config.backup,
+        Some(Server {
+            host: String::from("b"),
+            threads: 1
+        })
+    );
+    assert_eq!(config.hosts, ["a", "b"]);
+    assert_eq!(config.plugins, ["core", "extra"]);
+
+    let site = "<Config><hosts>c</hosts><plugins>more</plugins></Config>";
+    config.deserialize_str_in_place(site).unwrap();
+    assert_eq!(config.hosts, ["c"]);
+    assert_eq!(config.plugins, ["core", "extra", "more"]);
+
+    // fields read before the error stay overwritten
+    let site = "<Config port=\"1\" port=\"2\"/>";
+    assert!(matches!(
+        config.deserialize_str_in_place(site),
+        Err(XmlError::DuplicateField)
+    ));
+    assert_eq!(config.port, Some(1));
+    assert_eq!(config.hosts, ["c"]);
+}
+
+#[derive(Debug, DeXml, SerXml)]
+struct Document<'a> {
+    header: Lazy<'a, Id>,
+    body: Lazy<'a, User>,
+}
+
+#[test]
+fn derive_lazy() {
+    let xml = "<Document><header >7</header><body name=\"n\"   dname=\"d\"><odd-formatting/></body></Document>";
+    let mut doc = Document::deserialize_str(xml).unwrap();
+    assert!(!doc.header.is_parsed());
+    assert_eq!(
+        doc.body.raw(),
+        Some("<body name=\"n\"   dname=\"d\"><odd-formatting/></body>")
+    );
+    assert_eq!(doc.serialize_to_string(), xml);
+
+    assert!(doc.body.get().is_err());
+    doc.header.get_mut().unwrap().id += 1;
+    assert!(doc.header.is_parsed());
+    assert_eq!(
+        doc.serialize_to_string(),
+        "<Document><header>8</header><body name=\"n\"   dname=\"d\"><odd-formatting/></body></Document>"
+    );
+    assert_eq!(Lazy::new(Id { id: 1 }).into_inner().unwrap(), Id { id: 1 });
+
+    let doc = Document::deserialize_str("<Document><header>7</header><body/></Document>").unwrap();
+    let envelope = Envelope {
+        header: doc.header,
+        body: doc.body,
+    };
+    assert_eq!(
+        envelope.serialize_to_string(),
+        "<Envelope><head>7</head><content/></Envelope>"
+    );
+}
+
+#[derive(SerXml)]
+struct Envelope<'a> {
+    #[nanoxml(rename = "head")]
+    header: Lazy<'a, Id>,
+    #[nanoxml(rename = "content")]
+    body: Lazy<'a, User>,
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct Primitives {
+    #[nanoxml(attr)]
+    flag: bool,
+    #[nanoxml(attr)]
+    other: bool,
+    #[nanoxml(attr)]
+    int: i32,
+    #[nanoxml(attr)]
+    big: f64,
+    #[nanoxml(attr)]
+    small: f32,
+    #[nanoxml(attr)]
+    nan: f64,
+    float: f64,
+}
+
+#[test]
+fn derive_xsd_primitives() {
+    let xml = "<Primitives flag=\" 1 \" other=\"false\" int=\"&#43;&#49;2\" big=\"INF\" small=\"-INF\" nan=\"NaN\"><float>\n  1.5e3\n</float></Primitives>";
+    let p = Primitives::deserialize_str(xml).unwrap();
+    assert!(p.flag);
+    assert!(!p.other);
+    assert_eq!(p.int, 12);
+    assert_eq!(p.big, f64::INFINITY);
+    assert_eq!(p.small, f32::NEG_INFINITY);
+    assert!(p.nan.is_nan());
+    assert_eq!(p.float, 1500.0);
+    assert_eq!(
+        p.serialize_to_string(),
+        "<Primitives flag=\"true\" other=\"false\" int=\"12\" big=\"INF\" small=\"-INF\" nan=\"NaN\"><float>1.5E3</float></Primitives>"
+    );
+
+    for bad in ["inf", "infinity", "nan", "1 2", "yes", "0x10"] {
+        let xml = format!(
+            "<Primitives flag=\"1\" other=\"0\" int=\"1\" big=\"{bad}\" small=\"1\" nan=\"1\"><float>1</float></Primitives>"
+        );
+        assert!(
+            matches!(
+                Primitives::deserialize_str(&xml),
+                Err(XmlError::InvalidValue)
+            ),
+            "{bad}"
+        );
+    }
+
+    let padded = format!("<float>1&#46;5{}</float>", " ".repeat(70));
+    assert_eq!(f64::deserialize_str_as(&padded, "float").unwrap(), 1.5);
+    let long = format!("<float>0&#46;{}1</float>", "0".repeat(70));
+    assert_eq!(f64::deserialize_str_as(&long, "float").unwrap(), 1e-71);
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct StdTypes {
+    #[nanoxml(attr)]
+    sep: char,
+    #[nanoxml(attr)]
+    port: NonZeroU16,
+    #[nanoxml(attr)]
+    huge: u128,
+    addr: SocketAddr,
+    timeout: Duration,
+    name: Box<str>,
+    shared: Rc<str>,
+}
+
+#[test]
+fn derive_std_types() {
+    let xml = "<StdTypes sep=\"&lt;\" port=\"8080\" huge=\"340282366920938463463374607431768211455\"><addr>[::1]:443</addr><timeout>PT1M30.5S</timeout><name>a&amp;b</name><shared>c</shared></StdTypes>";
+    let t = StdTypes::deserialize_str(xml).unwrap();
+    assert_eq!(t.sep, '<');
+    assert_eq!(t.port.get(), 8080);
+    assert_eq!(t.huge, u128::MAX);
+    assert_eq!(t.addr.port(), 443);
+    assert_eq!(t.timeout, Duration::from_millis(90_500));
+    assert_eq!(&*t.name, "a&b");
+    assert_eq!(&*t.shared, "c");
+    assert_eq!(
+        t.serialize_to_string(),
+        "<StdTypes sep=\"&lt;\" port=\"8080\" huge=\"340282366920938463463374607431768211455\"><addr>[::1]:443</addr><timeout>PT1M30.5S</timeout><name>a&amp;b</name><shared>c</shared></StdTypes>"
+    );
+
+    assert_eq!(char::de_xml_attr(XmlStr::new(" ")).unwrap(), ' ');
+    assert!(char::de_xml_attr(XmlStr::new("ab")).is_err());
+    assert!(NonZeroU16::de_xml_attr(XmlStr::new("0")).is_err());
+    assert!(Duration::de_xml_attr(XmlStr::new("-PT1S")).is_err());
+
+    #[cfg(feature = "std")]
+    assert_eq!(
+        std::path::PathBuf::de_xml_attr(XmlStr::new("/tmp/x"))
+            .unwrap()
+            .to_str(),
+        Some("/tmp/x")
+    );
+}
+
 fn fourtytwo() -> i32 {
     42
 }
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct Node {
+    #[nanoxml(attr)]
+    label: Box<str>,
+    #[nanoxml(seq, rename = "node")]
+    children: Vec<Node>,
+    next: Option<Box<Node>>,
+    shared: Option<Rc<Id>>,
+}
+
+#[test]
+fn derive_recursive() {
+    let xml = "<Node label=\"root\"><node label=\"a\"/><node label=\"b\"><node label=\"c\"/></node><next label=\"d\"><shared>7</shared></next></Node>";
+    let node = Node::deserialize_str(xml).unwrap();
+    assert_eq!(node.children.len(), 2);
+    assert_eq!(&*node.children[1].children[0].label, "c");
+    let next = node.next.as_deref().unwrap();
+    assert_eq!(&*next.label, "d");
+    assert_eq!(next.shared.as_deref(), Some(&Id { id: 7 }));
+    assert_eq!(
+        node.serialize_to_string(),
+        "<Node label=\"root\"><node label=\"a\"></node><node label=\"b\"><node label=\"c\"></node></node><next label=\"d\"><shared>7</shared></next></Node>"
+    );
+    assert_eq!(*Box::<u8>::deserialize_str_as("<n>3</n>", "n").unwrap(), 3);
+
+    let xml =
+        "<Reading low=\"-3\" high=\"12\"><avg>4</avg><server threads=\"2\" host=\"a\"/></Reading>";
+    let mut reading = Reading::deserialize_str(xml).unwrap();
+    assert_eq!(*reading.low, Celsius(-3));
+    assert_eq!(*reading.high, Celsius(12));
+    assert_eq!(*reading.avg, Celsius(4));
+    reading
+        .deserialize_str_in_place("<Reading><server threads=\"8\"/></Reading>")
+        .unwrap();
+    assert_eq!(reading.server.host, "a");
+    assert_eq!(reading.server.threads, 8);
+
+    let backup = "<Reading><backup host=\"b\" threads=\"1\"/></Reading>";
+    reading.deserialize_str_in_place(backup).unwrap();
+    reading.deserialize_str_in_place(backup).unwrap();
+    assert_eq!(reading.backups.len(), 2);
+    let shared = Rc::clone(&reading.backups);
+    assert!(matches!(
+        reading.deserialize_str_in_place(backup),
+        Err(XmlError::AppendUnsupported)
+    ));
+    assert_eq!(shared.len(), 2);
+}
+
+#[derive(Debug, PartialEq)]
+struct Celsius(i32);
+
+impl<'a> DeXmlAttr<'a> for Celsius {
+    fn de_xml_attr(s: XmlStr<'a>) -> Result<Self, XmlError> {
+        i32::de_xml_attr(s).map(Celsius)
+    }
+}
+
+nanoxml::impl_de_element!(Celsius);
+
+#[derive(Debug, DeXml)]
+struct Reading {
+    #[nanoxml(attr)]
+    low: Box<Celsius>,
+    #[nanoxml(attr)]
+    high: Rc<Celsius>,
+    avg: Rc<Celsius>,
+    server: Rc<Server>,
+    #[nanoxml(seq, rename = "backup", merge = "append")]
+    backups: Rc<Vec<Server>>,
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct Collections {
+    #[nanoxml(seq, rename = "q")]
+    queue: VecDeque<u32>,
+    #[nanoxml(seq, rename = "t")]
+    tags: BTreeSet<String>,
+    #[nanoxml(seq, rename = "b")]
+    boxed: Box<[u8]>,
+    #[nanoxml(seq, rename = "l")]
+    list: LinkedList<bool>,
+}
+
+#[test]
+fn derive_collections() {
+    let xml = "<Collections><q>2</q><t>y</t><b>1</b><q>1</q><t>x</t><l>true</l></Collections>";
+    let c = Collections::deserialize_str(xml).unwrap();
+    assert_eq!(c.queue, [2, 1]);
+    assert_eq!(c.tags.iter().collect::<Vec<_>>(), ["x", "y"]);
+    assert_eq!(&*c.boxed, [1]);
+    assert_eq!(c.list.front(), Some(&true));
+    assert_eq!(
+        c.serialize_to_string(),
+        "<Collections><q>2</q><q>1</q><t>x</t><t>y</t><b>1</b><l>true</l></Collections>"
+    );
+
+    let xml = "<Collections><t>x</t><t>x</t></Collections>";
+    assert!(matches!(
+        Collections::deserialize_str(xml),
+        Err(XmlError::DuplicateItem)
+    ));
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct Settings {
+    #[nanoxml(map = "attr")]
+    flags: BTreeMap<String, bool>,
+    #[nanoxml(map = "entry", rename = "entry", key = "name")]
+    limits: BTreeMap<String, u32>,
+    #[nanoxml(map = "entry", rename = "quota")]
+    quotas: BTreeMap<u64, Quota>,
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct Quota {
+    #[nanoxml(attr)]
+    soft: u32,
+    #[nanoxml(text)]
+    hard: u32,
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct Env {
+    #[nanoxml(attr)]
+    name: String,
+    #[nanoxml(map = "tag")]
+    vars: BTreeMap<String, String>,
+}
+
+#[test]
+fn derive_maps() {
+    let xml = "<Settings debug=\"true\" fast=\"false\"><entry name=\"cpu\">4</entry><quota key=\"1\" soft=\"7\">9</quota><entry name=\"mem\">512</entry></Settings>";
+    let settings = Settings::deserialize_str(xml).unwrap();
+    assert_eq!(settings.flags.get("debug"), Some(&true));
+    assert_eq!(settings.limits.get("mem"), Some(&512));
+    assert_eq!(settings.quotas[&1], Quota { soft: 7, hard: 9 });
+    assert_eq!(
+        settings.serialize_to_string(),
+        "<Settings debug=\"true\" fast=\"false\"><entry name=\"cpu\">4</entry><entry name=\"mem\">512</entry><quota key=\"1\" soft=\"7\">9</quota></Settings>"
+    );
+
+    let xml = "<Env name=\"prod\"><HOME>/root</HOME><PATH>/bin</PATH></Env>";
+    let env = Env::deserialize_str(xml).unwrap();
+    assert_eq!(env.vars["PATH"], "/bin");
+    assert_eq!(env.serialize_to_string(), xml);
+
+    let xml = "<Env name=\"prod\"><HOME>/root</HOME><HOME>/home</HOME></Env>";
+    assert!(matches!(
+        Env::deserialize_str(xml),
+        Err(XmlError::DuplicateItem)
+    ));
+    let xml = "<Settings><quota soft=\"7\" key=\"1\">9</quota></Settings>";
+    let settings = Settings::deserialize_str(xml).unwrap();
+    assert_eq!(settings.quotas[&1], Quota { soft: 7, hard: 9 });
+    let xml = "<Settings><quota soft=\"7\" key=\"1\" key=\"2\">9</quota></Settings>";
+    assert!(matches!(
+        Settings::deserialize_str(xml),
+        Err(XmlError::InvalidField)
+    ));
+    let xml = "<Settings><entry>4</entry></Settings>";
+    assert!(matches!(
+        Settings::deserialize_str(xml),
+        Err(XmlError::MissingField)
+    ));
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct Shape {
+    #[nanoxml(attr, list)]
+    class: Vec<String>,
+    #[nanoxml(attr, list)]
+    origin: [i32; 2],
+    #[nanoxml(attr, list)]
+    tags: Option<Vec<String>>,
+    #[nanoxml(text, list)]
+    coords: Vec<f32>,
+}
+
+#[test]
+fn derive_lists() {
+    let xml = "<Shape class=\" big\n\tred \" origin=\"-1 2\">1.5 2 \n 3</Shape>";
+    let shape = Shape::deserialize_str(xml).unwrap();
+    assert_eq!(shape.class, ["big", "red"]);
+    assert_eq!(shape.origin, [-1, 2]);
+    assert_eq!(shape.tags, None);
+    assert_eq!(shape.coords, [1.5, 2.0, 3.0]);
+    assert_eq!(
+        shape.serialize_to_string(),
+        "<Shape class=\"big red\" origin=\"-1 2\">1.5E0 2.0E0 3.0E0</Shape>"
+    );
+
+    let xml = "<Shape class=\"\" origin=\"1\">1</Shape>";
+    assert!(matches!(
+        Shape::deserialize_str(xml),
+        Err(XmlError::SeqUnderflow)
+    ));
+    let xml = "<Shape class=\"\" origin=\"1 2 3\">1</Shape>";
+    assert!(matches!(
+        Shape::deserialize_str(xml),
+        Err(XmlError::SeqOverflow)
+    ));
+    let xml = "<Shape class=\"\" origin=\"1 x\">1</Shape>";
+    assert!(matches!(
+        Shape::deserialize_str(xml),
+        Err(XmlError::InvalidValue)
+    ));
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct Order {
+    #[nanoxml(seq, wrapper = "items", rename = "item")]
+    items: Vec<Id>,
+    #[nanoxml(seq, wrapper = "notes", rename = "note")]
+    notes: Vec<String>,
+}
+
+#[test]
+fn derive_wrapped_seq() {
+    let xml = "<Order><items><item>1</item><item>2</item></items><notes/></Order>";
+    let order = Order::deserialize_str(xml).unwrap();
+    assert_eq!(order.items, [Id { id: 1 }, Id { id: 2 }]);
+    assert!(order.notes.is_empty());
+    assert_eq!(
+        order.serialize_to_string(),
+        "<Order><items><item>1</item><item>2</item></items><notes></notes></Order>"
+    );
+
+    let mut order = Order::deserialize_str("<Order/>").unwrap();
+    assert!(order.items.is_empty());
+    order
+        .deserialize_str_in_place("<Order><items/></Order>")
+        .unwrap();
+    assert!(order.items.is_empty());
+
+    let xml = "<Order><items><other>1</other></items></Order>";
+    assert!(matches!(
+        Order::deserialize_str(xml),
+        Err(XmlError::InvalidField)
+    ));
+
+    let xml = "<Order><items xmlns:x=\"urn:x\"><item>3</item></items></Order>";
+    assert_eq!(Order::deserialize_str(xml).unwrap().items, [Id { id: 3 }]);
+
+    for items in [None, Some(vec![]), Some(vec![1, 2])] {
+        let order = OptionalOrder { items };
+        let xml = order.serialize_to_string();
+        assert_eq!(OptionalOrder::deserialize_str(&xml).unwrap(), order);
+    }
+    assert_eq!(
+        OptionalOrder { items: None }.serialize_to_string(),
+        "<OptionalOrder></OptionalOrder>"
+    );
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct OptionalOrder {
+    #[nanoxml(seq, wrapper = "items", rename = "item")]
+    items: Option<Vec<u32>>,
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct Para {
+    #[nanoxml(text)]
+    text: String,
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct Img {
+    #[nanoxml(attr)]
+    src: String,
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+enum BodyItem {
+    #[nanoxml(rename = "p")]
+    Para(Para),
+    #[nanoxml(rename = "img")]
+    Img(Img),
+    #[nanoxml(rename = "hr")]
+    Rule,
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+#[nanoxml(rename = "body")]
+struct Body {
+    #[nanoxml(attr)]
+    class: Option<String>,
+    #[nanoxml(seq)]
+    items: Vec<BodyItem>,
+    footer: Option<String>,
+}
+
+#[test]
+fn derive_heterogeneous_seq() {
+    let xml =
+        "<body class=\"main\"><p>a</p><img src=\"x.png\"/><hr/><p>b</p><footer>end</footer></body>";
+    let body = Body::deserialize_str(xml).unwrap();
+    assert_eq!(
+        body.items,
+        [
+            BodyItem::Para(Para { text: "a".into() }),
+            BodyItem::Img(Img {
+                src: "x.png".into()
+            }),
+            BodyItem::Rule,
+            BodyItem::Para(Para { text: "b".into() }),
+        ]
+    );
+    assert_eq!(body.footer.as_deref(), Some("end"));
+    assert_eq!(
+        body.serialize_to_string(),
+        "<body class=\"main\"><p>a</p><img src=\"x.png\"></img><hr/><p>b</p><footer>end</footer></body>"
+    );
+
+    let mut body = Body::deserialize_str("<body><hr/></body>").unwrap();
+    body.deserialize_str_in_place("<body><img src=\"y.png\"/></body>")
+        .unwrap();
+    assert_eq!(
+        body.items,
+        [BodyItem::Img(Img {
+            src: "y.png".into()
+        })]
+    );
+
+    assert!(matches!(
+        Body::deserialize_str("<body><table/></body>"),
+        Err(XmlError::InvalidField)
+    ));
+    assert!(matches!(
+        BodyItem::deserialize_str("<hr/>"),
+        Ok(BodyItem::Rule)
+    ));
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct Circle {
+    #[nanoxml(attr)]
+    r: u32,
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+enum Figure {
+    Circle(Circle),
+    Rect {
+        #[nanoxml(attr)]
+        w: u32,
+        #[nanoxml(attr)]
+        h: u32,
+        label: Option<String>,
+    },
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+#[nanoxml(unit = "element")]
+enum Access {
+    #[nanoxml(rename = "admin")]
+    Admin,
+    #[nanoxml(rename = "guest")]
+    Guest,
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct Drawing {
+    #[nanoxml(seq)]
+    shapes: Vec<Figure>,
+    access: Access,
+}
+
+#[test]
+fn derive_data_enums() {
+    let xml = "<Drawing><Rect w=\"2\" h=\"3\"/><Circle r=\"1\"/><Rect w=\"4\" h=\"5\"><label>big</label></Rect><access><admin/></access></Drawing>";
+    let mut drawing = Drawing::deserialize_str(xml).unwrap();
+    assert_eq!(
+        drawing.shapes,
+        [
+            Figure::Rect {
+                w: 2,
+                h: 3,
+                label: None
+            },
+            Figure::Circle(Circle { r: 1 }),
+            Figure::Rect {
+                w: 4,
+                h: 5,
+                label: Some("big".into())
+            },
+        ]
+    );
+    assert_eq!(drawing.access, Access::Admin);
+    assert_eq!(
+        drawing.serialize_to_string(),
+        "<Drawing><Rect w=\"2\" h=\"3\"></Rect><Circle r=\"1\"></Circle><Rect w=\"4\" h=\"5\"><label>big</label></Rect><access><admin/></access></Drawing>"
+    );
+
+    drawing
+        .deserialize_str_in_place("<Drawing><access><guest/></access></Drawing>")
+        .unwrap();
+    assert_eq!(drawing.access, Access::Guest);
+    assert_eq!(drawing.shapes.len(), 3);
+
+    assert_eq!(
+        Figure::deserialize_str("<Rect h=\"1\" w=\"2\"/>").unwrap(),
+        Figure::Rect {
+            w: 2,
+            h: 1,
+            label: None
+        }
+    );
+    assert!(matches!(
+        Figure::deserialize_str("<Rect w=\"2\"/>"),
+        Err(XmlError::MissingField)
+    ));
+    assert!(matches!(
+        Figure::deserialize_str("<Rect w=\"2\" h=\"1\" d=\"3\"/>"),
+        Err(XmlError::InvalidField)
+    ));
+    assert_eq!(Access::deserialize_str("<guest/>").unwrap(), Access::Guest);
+    let mut access = Access::Guest;
+    access.deserialize_str_in_place("<admin/>").unwrap();
+    assert_eq!(access, Access::Admin);
+    access
+        .deserialize_str_in_place("<Access><guest/></Access>")
+        .unwrap();
+    assert_eq!(access, Access::Guest);
+    assert!(matches!(
+        access.deserialize_str_in_place("<other/>"),
+        Err(XmlError::NameMismatch)
+    ));
+    assert!(Access::deserialize_str("<Access>guest</Access>").is_err());
+    assert_eq!(Access::Guest.serialize_to_string(), "<guest/>");
+    assert_eq!(
+        Access::Guest.serialize_to_string_as("role"),
+        "<role><guest/></role>"
+    );
+    assert_eq!(
+        Access::deserialize_str_as("<role><guest/></role>", "role").unwrap(),
+        Access::Guest
+    );
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct Pair {
+    a: Figure,
+    #[nanoxml(rename = "second")]
+    b: Option<Figure>,
+}
+
+#[derive(DeXml, SerXml)]
+struct LazyPair<'a> {
+    a: Lazy<'a, Figure>,
+}
+
+#[test]
+fn derive_data_enum_fields() {
+    let pair = Pair {
+        a: Figure::Circle(Circle { r: 1 }),
+        b: Some(Figure::Circle(Circle { r: 2 })),
+    };
+    let xml = pair.serialize_to_string();
+    assert_eq!(
+        xml,
+        "<Pair><a><Circle r=\"1\"></Circle></a><second><Circle r=\"2\"></Circle></second></Pair>"
+    );
+    assert_eq!(Pair::deserialize_str(&xml).unwrap(), pair);
+
+    let mut pair = Pair::deserialize_str("<Pair><a><Circle r=\"3\"/></a></Pair>").unwrap();
+    assert_eq!(pair.b, None);
+    pair.deserialize_str_in_place(
+        "<Pair><second><Rect w=\"1\" h=\"2\"/></second><a><Circle r=\"4\"/></a></Pair>",
+    )
+    .unwrap();
+    assert_eq!(pair.a, Figure::Circle(Circle { r: 4 }));
+    assert_eq!(
+        pair.b,
+        Some(Figure::Rect {
+            w: 1,
+            h: 2,
+            label: None
+        })
+    );
+
+    assert!(matches!(
+        Pair::deserialize_str("<Pair><Circle r=\"1\"/></Pair>"),
+        Err(XmlError::InvalidField)
+    ));
+    assert!(matches!(
+        Pair::deserialize_str("<Pair><a><Square/></a></Pair>"),
+        Err(XmlError::InvalidVariant)
+    ));
+    assert!(matches!(
+        Pair::deserialize_str("<Pair><a/></Pair>"),
+        Err(XmlError::MissingField)
+    ));
+
+    let xml = "<LazyPair><a><Circle r=\"5\"/></a></LazyPair>";
+    let lazy = LazyPair::deserialize_str(xml).unwrap();
+    assert_eq!(lazy.serialize_to_string(), xml);
+    assert_eq!(lazy.a.get().unwrap(), &Figure::Circle(Circle { r: 5 }));
+    assert_eq!(
+        lazy.serialize_to_string(),
+        "<LazyPair><a><Circle r=\"5\"></Circle></a></LazyPair>"
+    );
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+#[nanoxml(tag = "type")]
+enum Outline {
+    #[nanoxml(rename = "circle")]
+    Circle {
+        #[nanoxml(attr)]
+        r: u32,
+    },
+    #[nanoxml(rename = "poly")]
+    Polygon {
+        #[nanoxml(seq, rename = "pt")]
+        points: Vec<u32>,
+    },
+    #[nanoxml(rename = "none")]
+    Empty,
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+#[nanoxml(tag = "xsi:type")]
+enum Value {
+    #[nanoxml(rename = "xs:int")]
+    Int {
+        #[nanoxml(text)]
+        value: i32,
+    },
+    #[nanoxml(rename = "xs:string")]
+    Str {
+        #[nanoxml(text)]
+        value: String,
+    },
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct Canvas {
+    #[nanoxml(seq)]
+    shape: Vec<Outline>,
+    value: Value,
+}
+
+#[test]
+fn derive_tagged_enums() {
+    let xml = "<Canvas><shape r=\"3\" type=\"circle\"/><shape type=\"poly\"><pt>1</pt><pt>2</pt></shape><shape type=\"none\"/><value xsi:type=\"xs:int\">42</value></Canvas>";
+    let canvas = Canvas::deserialize_str(xml).unwrap();
+    assert_eq!(
+        canvas.shape,
+        [
+            Outline::Circle { r: 3 },
+            Outline::Polygon { points: vec![1, 2] },
+            Outline::Empty,
+        ]
+    );
+    assert_eq!(canvas.value, Value::Int { value: 42 });
+    assert_eq!(
+        canvas.serialize_to_string(),
+        "<Canvas><shape type=\"circle\" r=\"3\"></shape><shape type=\"poly\"><pt>1</pt><pt>2</pt></shape><shape type=\"none\"></shape><value xsi:type=\"xs:int\">42</value></Canvas>"
+    );
+
+    assert_eq!(
+        Value::deserialize_str("<Value xsi:type=\"xs:string\">hi</Value>").unwrap(),
+        Value::Str { value: "hi".into() }
+    );
+    assert!(matches!(
+        Outline::deserialize_str("<Outline r=\"3\"/>"),
+        Err(XmlError::MissingField)
+    ));
+    assert!(matches!(
+        Outline::deserialize_str("<Outline type=\"square\"/>"),
+        Err(XmlError::InvalidVariant)
+    ));
+    assert!(matches!(
+        Outline::deserialize_str("<Outline type=\"none\" r=\"3\"/>"),
+        Err(XmlError::InvalidField)
+    ));
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct Measure {
+    #[nanoxml(attr)]
+    double: f64,
+    #[nanoxml(attr)]
+    single: f32,
+}
+
+#[test]
+fn derive_canonical_floats() {
+    let cases = [
+        (
+            0.0,
+            0.0,
+            "<Measure double=\"0.0E0\" single=\"0.0E0\"></Measure>",
+        ),
+        (
+            -0.0,
+            -0.0,
+            "<Measure double=\"-0.0E0\" single=\"-0.0E0\"></Measure>",
+        ),
+        (
+            1.0,
+            100.0,
+            "<Measure double=\"1.0E0\" single=\"1.0E2\"></Measure>",
+        ),
+        (
+            -0.00125,
+            0.1,
+            "<Measure double=\"-1.25E-3\" single=\"1.0E-1\"></Measure>",
+        ),
+        (
+            f64::MAX,
+            f32::MIN_POSITIVE,
+            "<Measure double=\"1.7976931348623157E308\" single=\"1.1754944E-38\"></Measure>",
+        ),
+    ];
+    for (double, single, xml) in cases {
+        let measure = Measure { double, single };
+        assert_eq!(measure.serialize_to_string(), xml);
+        let parsed = Measure::deserialize_str(xml).unwrap();
+        assert_eq!(parsed.double.to_bits(), double.to_bits());
+        assert_eq!(parsed.single.to_bits(), single.to_bits());
+    }
+}
@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use nanoxml::derive::de::{DeXml, DeXmlTopLevel};
+use nanoxml::derive::ser::{SerXml, SerXmlTopLevel};
+use nanoxml::xsd::{Base64, Hex, XsDate, XsDateTime, XsDuration, XsTime};
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct Event {
+    #[nanoxml(attr)]
+    start: XsDateTime,
+    #[nanoxml(attr)]
+    day: XsDate,
+    at: XsTime,
+    length: XsDuration,
+}
+
+#[test]
+fn date_time() {
+    let xml = "<Event start=\"2024-02-29T23:30:00.250+02:00\" day=\"-0044-03-15Z\"><at>24:00:00</at><length>P1Y2M3DT4H5M6.7S</length></Event>";
+    let event = Event::deserialize_str(xml).unwrap();
+    assert_eq!(event.start.nanosecond, 250_000_000);
+    assert_eq!(event.start.timezone, Some(120));
+    assert_eq!(event.day.year, -44);
+    assert_eq!(event.at.hour, 0);
+    assert_eq!(event.length.seconds, 6);
+    assert_eq!(event.length.nanoseconds, 700_000_000);
+    assert_eq!(
+        event.serialize_to_string(),
+        "<Event start=\"2024-02-29T23:30:00.25+02:00\" day=\"-0044-03-15Z\"><at>00:00:00</at><length>P1Y2M3DT4H5M6.7S</length></Event>"
+    );
+
+    let utc = event.start.to_utc();
+    assert_eq!(utc.to_string(), "2024-02-29T21:30:00.25Z");
+    assert_eq!(
+        utc.to_timezone(-600).to_string(),
+        "2024-02-29T11:30:00.25-10:00"
+    );
+    assert_eq!(
+        event.start.to_timezone(180).to_string(),
+        "2024-03-01T00:30:00.25+03:00"
+    );
+    let midnight: XsDateTime = "1999-12-31T24:00:00".parse().unwrap();
+    assert_eq!(midnight.to_string(), "2000-01-01T00:00:00");
+
+    for bad in [
+        "2023-02-29T00:00:00",
+        "2024-1-01T00:00:00",
+        "02024-01-01T00:00:00",
+        "2024-01-01T24:00:01",
+        "2024-01-01T00:60:00",
+        "2024-01-01T00:00:00+14:30",
+        "2024-01-01T00:00:00.",
+        "2024-01-01 00:00:00",
+    ] {
+        assert!(bad.parse::<XsDateTime>().is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn duration() {
+    let d: XsDuration = "-P1M".parse().unwrap();
+    assert!(d.negative);
+    assert_eq!(d.months, 1);
+    assert_eq!(d.to_string(), "-P1M");
+    assert_eq!("PT0S".parse::<XsDuration>().unwrap(), XsDuration::default());
+    assert_eq!(XsDuration::default().to_string(), "PT0S");
+
+    let d: XsDuration = "P2DT1H0.5S".parse().unwrap();
+    assert_eq!(
+        Duration::try_from(d).unwrap(),
+        Duration::from_millis(2 * 86_400_000 + 3_600_000 + 500)
+    );
+    assert!(Duration::try_from("P1Y".parse::<XsDuration>().unwrap()).is_err());
+    assert!(Duration::try_from("-PT1S".parse::<XsDuration>().unwrap()).is_err());
+    assert_eq!(
+        XsDuration::from(Duration::from_secs(90061)).to_string(),
+        "P1DT1H1M1S"
+    );
+
+    for bad in [
+        "P", "PT", "P1DT", "P1S", "PT1D", "P1M1Y", "P1.5D", "1D", "P-1D",
+    ] {
+        assert!(bad.parse::<XsDuration>().is_err(), "{bad}");
+    }
+}
+
+#[derive(Debug, DeXml, PartialEq, SerXml)]
+struct Blob {
+    #[nanoxml(attr)]
+    digest: Hex<[u8; 4]>,
+    data: Base64<Vec<u8>>,
+}
+
+#[test]
+fn binary() {
+    let xml = "<Blob digest=\"deadBEEF\"><data>\n  aGVsbG8g\n  d29ybGQ=\n</data></Blob>";
+    let blob = Blob::deserialize_str(xml).unwrap();
+    assert_eq!(blob.digest, Hex([0xde, 0xad, 0xbe, 0xef]));
+    assert_eq!(blob.data.0, b"hello world");
+    assert_eq!(
+        blob.serialize_to_string(),
+        "<Blob digest=\"DEADBEEF\"><data>aGVsbG8gd29ybGQ=</data></Blob>"
+    );
+
+    assert!(Blob::deserialize_str("<Blob digest=\"dead\"><data></data></Blob>").is_err());
+    assert!(Blob::deserialize_str("<Blob digest=\"deadbeef00\"><data></data></Blob>").is_err());
+    assert!(Blob::deserialize_str("<Blob digest=\"deadbeef\"><data>aGk</data></Blob>").is_err());
+    assert!(Blob::deserialize_str("<Blob digest=\"deadbeef\"><data>aG=k</data></Blob>").is_err());
+}